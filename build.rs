@@ -0,0 +1,53 @@
+//! Record where the program was built from, for `reps --version`.
+
+use std::env;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Short hash of the commit being built, if it is built from a git repository
+fn git_hash() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = String::from_utf8(output.stdout).ok()?;
+    Some(hash.trim().to_string()).filter(|hash| !hash.is_empty())
+}
+
+/// Date of the build as YYYY-MM-DD, from SOURCE_DATE_EPOCH for reproducible builds
+fn build_date() -> String {
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        });
+    // Days since 1970-01-01 to a date of the proleptic Gregorian calendar
+    let days = (seconds / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn main() {
+    let info = match git_hash() {
+        Some(hash) => format!("commit {}, built {}", hash, build_date()),
+        None => format!("built {}", build_date()),
+    };
+    println!("cargo:rustc-env=REPS_BUILD_INFO={}", info);
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
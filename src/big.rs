@@ -0,0 +1,45 @@
+//! Large digits, readable from across the room.
+
+/// Height of the digits, in lines
+pub const HEIGHT: usize = 7;
+
+/// 5x7 font for the digits from 0 to 9
+#[rustfmt::skip]
+const FONT: [[&str; HEIGHT]; 10] = [
+    [" ### ", "#   #", "#  ##", "# # #", "##  #", "#   #", " ### "],
+    ["  #  ", " ##  ", "  #  ", "  #  ", "  #  ", "  #  ", " ### "],
+    [" ### ", "#   #", "    #", "   # ", "  #  ", " #   ", "#####"],
+    ["#####", "   # ", "  #  ", "   # ", "    #", "#   #", " ### "],
+    ["   # ", "  ## ", " # # ", "#  # ", "#####", "   # ", "   # "],
+    ["#####", "#    ", "#### ", "    #", "    #", "#   #", " ### "],
+    ["  ## ", " #   ", "#    ", "#### ", "#   #", "#   #", " ### "],
+    ["#####", "    #", "   # ", "  #  ", " #   ", " #   ", " #   "],
+    [" ### ", "#   #", "#   #", " ### ", "#   #", "#   #", " ### "],
+    [" ### ", "#   #", "#   #", " ####", "    #", "   # ", " ##  "],
+];
+
+/// Separator of the minutes and seconds, e.g. in "1:30"
+#[rustfmt::skip]
+const COLON: [&str; HEIGHT] = [" ", " ", "#", " ", "#", " ", " "];
+
+/// Render the digits and colons of the text with the large font, and return the lines to
+/// display. All the lines have the same width.
+pub fn render(text: &str) -> Vec<String> {
+    let glyphs: Vec<&[&str; HEIGHT]> = text
+        .bytes()
+        .filter_map(|c| match c {
+            b'0'..=b'9' => Some(&FONT[(c - b'0') as usize]),
+            b':' => Some(&COLON),
+            _ => None,
+        })
+        .collect();
+    (0..HEIGHT)
+        .map(|row| {
+            glyphs
+                .iter()
+                .map(|glyph| glyph[row].replace('#', "█"))
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .collect()
+}
@@ -0,0 +1,1524 @@
+//! Command line parsing.
+
+use std::env;
+use std::io::{stdin, stdout, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{load_exercises, load_phrases, load_plan, load_theme};
+use crate::history;
+use crate::presets;
+use crate::resume;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use reps::{
+    check_duration, parse_duration, CueIntensity, Exercise, Keys, Mode, Options, Palette, Phase,
+    Schedule, Theme, MOTIVATION,
+};
+
+/// Duration of each rep when it is not always `rep_time`, or nothing otherwise.
+fn rep_times(matches: &ArgMatches, num_reps: u32, rep_time: u32) -> Vec<u32> {
+    if let Some(durations) = matches.get_many::<u32>("durations") {
+        return durations.copied().collect();
+    }
+    let reps = 0..num_reps;
+    match (
+        matches.get_one::<u32>("pyramid"),
+        matches.get_one::<u32>("ladder"),
+    ) {
+        // Longer as the rep gets away from both ends
+        (Some(step), _) => reps
+            .map(|index| rep_time + step * index.min(num_reps - 1 - index))
+            .collect(),
+        (None, Some(step)) => reps.map(|index| rep_time + step * index).collect(),
+        (None, None) => vec![],
+    }
+}
+
+/// Most reps, rounds, sets... accepted, so that the duration of the workout stays reasonable
+const MAX_COUNT: i64 = 10_000;
+
+/// Fastest cadence accepted, in beats per minute: each beat of 200ms still spans a few polls of
+/// the keyboard, flashing the screen and restoring it in time for the next one
+const MAX_CADENCE: i64 = 300;
+
+/// Parse the duration of a countdown, which must not be longer than `MAX_DURATION`.
+fn parse_time(s: &str) -> Result<u32, String> {
+    check_duration(s, parse_duration(s)?, false)
+}
+
+/// Parse the duration of the work, which must not be 0 either.
+fn parse_work_time(s: &str) -> Result<u32, String> {
+    check_duration(s, parse_duration(s)?, true)
+}
+
+/// Parse a range of durations, e.g. "30:90" or "1m:2m".
+fn parse_range(s: &str) -> Result<(u32, u32), String> {
+    let (min, max) = s
+        .split_once(':')
+        .ok_or_else(|| format!("\"{}\" is not a range, e.g. \"30:90\"", s))?;
+    check_range(s, (parse_time(min)?, parse_time(max)?))
+}
+
+/// Check that the minimum of the range written `text` is not above its maximum.
+fn check_range(text: &str, (min, max): (u32, u32)) -> Result<(u32, u32), String> {
+    if min > max {
+        return Err(format!("the minimum of \"{}\" is above the maximum", text));
+    }
+    Ok((min, max))
+}
+
+/// Parse an exercise of a circuit and its duration, e.g. "squats:45" or "plank:1m".
+fn parse_station(s: &str) -> Result<(String, u32), String> {
+    match s.split_once(':') {
+        Some((name, duration)) if !name.is_empty() => {
+            Ok((name.to_string(), parse_work_time(duration)?))
+        }
+        _ => Err(format!("\"{}\" is not an exercise, e.g. \"squats:45\"", s)),
+    }
+}
+
+/// Keys which always do the same thing: adjusting the time, displaying the help and muting
+const FIXED_KEYS: &[u8] = b"+-?m";
+
+/// Parse a key controlling the countdowns: a character, or the name of a special key.
+fn parse_key(s: &str) -> Result<u8, String> {
+    let key = match s.to_lowercase().as_str() {
+        "esc" | "escape" => 27,
+        "space" => b' ',
+        "tab" => b'\t',
+        "enter" => b'\r',
+        _ => match s.as_bytes() {
+            [key] if key.is_ascii_graphic() => *key,
+            _ => {
+                return Err(format!(
+                    "\"{}\" is not a key, e.g. \"q\", \"esc\", \"space\", \"tab\" or \"enter\"",
+                    s
+                ))
+            }
+        },
+    };
+    if FIXED_KEYS.contains(&key) {
+        return Err(format!(
+            "\"{}\" is already used to adjust the time, to display the help or to mute",
+            s
+        ));
+    }
+    Ok(key)
+}
+
+/// Check that each key does a single thing.
+fn check_keys(keys: &Keys) -> Result<(), String> {
+    let actions = [
+        ("pause", keys.pause),
+        ("quit", keys.quit),
+        ("skip", keys.skip),
+        ("restart", keys.restart),
+        ("go back", keys.previous),
+        ("end the rest", keys.ready),
+    ];
+    for (i, (action, key)) in actions.iter().enumerate() {
+        if let Some((other, _)) = actions[i + 1..].iter().find(|(_, other)| other == key) {
+            return Err(format!(
+                "The same key, {}, cannot be used to {} and to {}",
+                Keys::name(*key),
+                action,
+                other
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Parse the speed of the clock, which must be a positive number.
+fn parse_speed(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .map_err(|_| format!("\"{}\" is not a positive number", s))
+        .and_then(check_speed)
+}
+
+fn check_speed(speed: f64) -> Result<f64, String> {
+    if speed.is_finite() && speed > 0.0 {
+        Ok(speed)
+    } else {
+        Err(format!("\"{}\" is not a positive number", speed))
+    }
+}
+
+/// Check the options of a workout saved in a file, e.g. a preset, as they would have been on the
+/// command line: the file may have been edited since.
+fn check_saved(opts: Options) -> Result<Options, String> {
+    let invalid = |field: &str, e: String| format!("Invalid saved workout: {}: {}", field, e);
+    let duration = |field: &str, seconds: u32, work: bool| {
+        check_duration(&seconds.to_string(), seconds, work).map_err(|e| invalid(field, e))
+    };
+    check_speed(opts.speed).map_err(|e| invalid("speed", e))?;
+    if let Some((min, max)) = opts.relax_range {
+        check_range(&format!("{}:{}", min, max), (min, max))
+            .map_err(|e| invalid("relax_range", e))?;
+    }
+    for (field, seconds) in [
+        ("rep_time", opts.rep_time),
+        ("relax_time", opts.relax_time),
+        ("prep_time", opts.prep_time),
+        ("set_rest", opts.set_rest),
+        ("long_break", opts.long_break),
+        ("warmup", opts.warmup),
+        ("cooldown", opts.cooldown),
+        ("max_duration", opts.max_duration.unwrap_or(0)),
+    ] {
+        duration(field, seconds, false)?;
+    }
+    for &seconds in &opts.rep_times {
+        duration("rep_times", seconds, true)?;
+    }
+    for &seconds in &opts.relax_times {
+        duration("relax_times", seconds, false)?;
+    }
+    for exercise in &opts.exercises {
+        duration("rep_time", exercise.rep_time, true)?;
+        duration("relax_time", exercise.relax_time, false)?;
+    }
+    for phase in opts.schedule.iter().flat_map(|schedule| &schedule.phases) {
+        duration(
+            "schedule",
+            phase.duration(),
+            matches!(phase, Phase::Rep { .. }),
+        )?;
+    }
+    for (field, count) in [
+        ("num_reps", opts.num_reps),
+        ("sets", opts.sets),
+        ("long_break_every", opts.long_break_every),
+    ] {
+        if count as i64 > MAX_COUNT {
+            return Err(invalid(field, format!("{} is above {}", count, MAX_COUNT)));
+        }
+    }
+    if opts.cadence as i64 > MAX_CADENCE {
+        let error = format!("{} is above {}", opts.cadence, MAX_CADENCE);
+        return Err(invalid("cadence", error));
+    }
+    Ok(opts)
+}
+
+/// Ask the user a yes or no question on the terminal.
+fn ask(question: &str) -> Result<bool, clap::Error> {
+    print!("{} (y/n) ", question);
+    let mut answer = String::new();
+    stdout()
+        .flush()
+        .and_then(|()| stdin().read_line(&mut answer))
+        .map_err(|e| {
+            clap::Error::raw(ErrorKind::Io, format!("Could not read the answer: {}\n", e))
+        })?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Environment variables giving the default of a duration, e.g. `REPS_PREP=5`
+const ENV_DURATIONS: [(&str, &str); 5] = [
+    ("REPS_RELAX_TIME", "relax_time"),
+    ("REPS_PREP", "prep_time"),
+    ("REPS_SET_REST", "set_rest"),
+    ("REPS_WARMUP", "warmup"),
+    ("REPS_COOLDOWN", "cooldown"),
+];
+/// Environment variables turning a flag on by default, e.g. `REPS_NO_COLOR=1`
+const ENV_FLAGS: [(&str, &str); 4] = [
+    ("REPS_NO_COLOR", "no_color"),
+    ("REPS_BELL", "bell"),
+    ("REPS_BIG", "big"),
+    ("REPS_CONFIRM_EXIT", "confirm_exit"),
+];
+
+/// Value of an environment variable, if it is set and not empty.
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Parse the value of an environment variable turning a flag on or off.
+fn parse_switch(s: &str) -> Result<bool, String> {
+    match s.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("\"{}\" is neither 1 nor 0", s)),
+    }
+}
+
+/// Defaults given by the environment variables, parsed once before reading the arguments.
+#[derive(Default)]
+struct EnvDefaults {
+    durations: Vec<(&'static str, u32)>,
+    flags: Vec<(&'static str, bool)>,
+}
+
+impl EnvDefaults {
+    fn load() -> Result<EnvDefaults, String> {
+        let invalid = |name: &str, e: String| format!("Invalid {}: {}", name, e);
+        let mut defaults = EnvDefaults::default();
+        for (name, id) in ENV_DURATIONS {
+            if let Some(value) = env_value(name) {
+                let duration = parse_time(&value).map_err(|e| invalid(name, e))?;
+                defaults.durations.push((id, duration));
+            }
+        }
+        for (name, id) in ENV_FLAGS {
+            if let Some(value) = env_value(name) {
+                let on = parse_switch(&value).map_err(|e| invalid(name, e))?;
+                defaults.flags.push((id, on));
+            }
+        }
+        Ok(defaults)
+    }
+
+    /// Default of the duration `id` given by the environment, if any.
+    fn duration(&self, id: &str) -> Option<u32> {
+        self.durations
+            .iter()
+            .find(|(name, _)| *name == id)
+            .map(|&(_, duration)| duration)
+    }
+
+    /// Value of the duration `id`: the command line still has the last word.
+    fn get_duration(&self, matches: &ArgMatches, id: &str) -> u32 {
+        match self.duration(id) {
+            Some(duration) if matches.value_source(id) != Some(ValueSource::CommandLine) => {
+                duration
+            }
+            _ => *matches.get_one::<u32>(id).unwrap(),
+        }
+    }
+
+    /// Value of the flag `id`: given on the command line, it is on whatever the environment says.
+    fn get_flag(&self, matches: &ArgMatches, id: &str) -> bool {
+        matches.get_flag(id)
+            || self
+                .flags
+                .iter()
+                .find(|(name, _)| *name == id)
+                .is_some_and(|&(_, on)| on)
+    }
+}
+
+/// Version printed by `--version`, with where it was built from, e.g.
+/// "0.1.0 (commit 1a2b3c4, built 2024-05-01)"
+const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("REPS_BUILD_INFO"),
+    ")"
+);
+
+fn command() -> Command {
+    Command::new("Reps")
+        .version(env!("CARGO_PKG_VERSION"))
+        .long_version(LONG_VERSION)
+        .author("Simon M. <git@simon.marache.net>")
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .arg(
+            Arg::new("num_reps")
+                .help("Number of reps, 0 to go on until ESC is pressed")
+                .value_parser(value_parser!(u32).range(0..=MAX_COUNT))
+                .required_unless_present_any([
+                    "config",
+                    "preset",
+                    "import",
+                    "list_presets",
+                    "repeat_last",
+                    "resume",
+                    "durations",
+                    "plan",
+                    "until_stop",
+                ])
+                .conflicts_with_all(["durations", "until_stop"]),
+        )
+        .arg(
+            Arg::new("rep_time")
+                .help("Duration of each rep, e.g. 30, 1m30s or 1:30")
+                .value_parser(parse_work_time)
+                .required_unless_present_any([
+                    "config",
+                    "preset",
+                    "import",
+                    "list_presets",
+                    "repeat_last",
+                    "resume",
+                    "durations",
+                    "plan",
+                    "until_stop",
+                ])
+                .conflicts_with_all(["durations", "until_stop"]),
+        )
+        .arg(
+            Arg::new("relax_time")
+                .help("Duration of the relax after each rep [default: 0, for back-to-back reps]")
+                .value_parser(parse_time),
+        )
+        .arg(
+            Arg::new("durations")
+                .long("durations")
+                .value_name("DURATIONS")
+                .help("Duration of each rep, e.g. \"20,30,40\", instead of num_reps and rep_time")
+                .value_parser(parse_work_time)
+                .value_delimiter(',')
+                .conflicts_with_all(["config", "pyramid", "ladder"]),
+        )
+        .arg(
+            Arg::new("relaxes")
+                .long("relaxes")
+                .value_name("DURATIONS")
+                .help("Duration of the relax after each rep of --durations, or after all of them")
+                .value_parser(parse_time)
+                .value_delimiter(',')
+                .requires("durations")
+                .conflicts_with_all(["num_reps", "relax_range"]),
+        )
+        .arg(
+            Arg::new("pyramid")
+                .long("pyramid")
+                .value_name("STEP")
+                .help("Make the reps longer by STEP up to the middle one, then shorter again")
+                .value_parser(parse_time)
+                .conflicts_with_all(["config", "ladder"]),
+        )
+        .arg(
+            Arg::new("ladder")
+                .long("ladder")
+                .value_name("STEP")
+                .help("Make each rep longer than the previous one by STEP")
+                .value_parser(parse_time)
+                .conflicts_with("config"),
+        )
+        .arg(
+            Arg::new("until_stop")
+                .long("until-stop")
+                .value_names(["REP_TIME", "RELAX_TIME"])
+                .help("Go on with reps of REP_TIME until ESC is pressed, relaxing RELAX_TIME after each one")
+                .value_parser(parse_time)
+                .num_args(1..=2)
+                .conflicts_with_all(["config", "durations", "pyramid", "ladder", "relax_time"]),
+        )
+        .arg(
+            Arg::new("relax_range")
+                .long("relax-range")
+                .value_name("MIN:MAX")
+                .help("Relax for a random duration between MIN and MAX, e.g. 30:90 or 1m:2m")
+                .value_parser(parse_range)
+                .conflicts_with_all(["relax_time", "config"]),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("TOML file describing the exercises of the workout")
+                .value_parser(value_parser!(PathBuf))
+                .conflicts_with_all(["num_reps", "rep_time", "relax_time"]),
+        )
+        .arg(
+            Arg::new("preset")
+                .long("preset")
+                .value_name("NAME")
+                .help("Run the workout saved with --save NAME")
+                .conflicts_with_all(["num_reps", "rep_time", "relax_time", "config"]),
+        )
+        .arg(
+            Arg::new("save")
+                .long("save")
+                .value_name("NAME")
+                .help("Save the workout as a preset, to run it again later with --preset NAME")
+                .global(true),
+        )
+        .arg(
+            Arg::new("repeat_last")
+                .long("repeat-last")
+                .help("Run the last workout completed again, with the same options")
+                .action(ArgAction::SetTrue)
+                .exclusive(true),
+        )
+        .arg(
+            Arg::new("resumable")
+                .long("resumable")
+                .help("Save the position in the workout as it goes, to resume it with --resume if it is interrupted")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .help("Go on with the workout run with --resumable that was interrupted")
+                .action(ArgAction::SetTrue)
+                .exclusive(true),
+        )
+        .arg(
+            Arg::new("list_presets")
+                .long("list-presets")
+                .help("Print the names of the saved presets, and exit")
+                .action(ArgAction::SetTrue)
+                .exclusive(true),
+        )
+        .arg(
+            Arg::new("names")
+                .long("names")
+                .value_name("NAMES")
+                .help("Comma-separated names displayed for each rep, e.g. \"Push-ups,Plank\"")
+                .value_delimiter(',')
+                .conflicts_with("config"),
+        )
+        .arg(
+            Arg::new("rep_message")
+                .long("rep-msg")
+                .value_name("MESSAGE")
+                .help("Message displayed during the reps instead of \"Rep {n}/{total}\"")
+                .global(true),
+        )
+        .arg(
+            Arg::new("relax_message")
+                .long("relax-msg")
+                .value_name("MESSAGE")
+                .help("Message displayed during the relax instead of \"Relax!\"")
+                .global(true),
+        )
+        .arg(
+            Arg::new("prep_time")
+                .long("prep")
+                .help("Duration of the countdown before the first rep, 0 to disable it")
+                .value_parser(parse_time)
+                .default_value("3")
+                .global(true),
+        )
+        .arg(
+            Arg::new("sets")
+                .long("sets")
+                .help("Number of times the whole workout is done")
+                .value_parser(value_parser!(u32).range(1..=MAX_COUNT))
+                .default_value("1")
+                .global(true),
+        )
+        .arg(
+            // Not global, the subcommands have their own rounds
+            Arg::new("workout_rounds")
+                .long("rounds")
+                .value_name("ROUNDS")
+                .help("Same as --sets, but each set is called a round, e.g. \"Round 1/3\"")
+                .value_parser(value_parser!(u32).range(1..=MAX_COUNT))
+                .conflicts_with_all(["sets", "preset", "repeat_last", "resume", "import", "plan"]),
+        )
+        .arg(
+            Arg::new("set_rest")
+                .long("set-rest")
+                .help("Duration of the rest between two sets")
+                .value_parser(parse_time)
+                .default_value("60")
+                .global(true),
+        )
+        .arg(
+            Arg::new("warmup")
+                .long("warmup")
+                .help("Duration of the warmup before the first rep")
+                .value_parser(parse_time)
+                .default_value("0")
+                .global(true),
+        )
+        .arg(
+            Arg::new("cooldown")
+                .long("cooldown")
+                .help("Duration of the cooldown after the last rep")
+                .value_parser(parse_time)
+                .default_value("0")
+                .global(true),
+        )
+        .arg(
+            Arg::new("shuffle")
+                .long("shuffle")
+                .help("Go through the exercises of --config or of a circuit in a random order")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed of --shuffle and --relax-range, to get the same order and durations again")
+                .value_parser(value_parser!(u64))
+                .global(true),
+        )
+        .arg(
+            Arg::new("confirm_exit")
+                .long("confirm-exit")
+                .help("Ask for a confirmation before exiting with ESC or Ctrl-C")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("wait_for_key")
+                .long("wait-for-key")
+                .help("Wait for a key to be pressed before starting the workout")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["json", "quiet"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("no_confirm")
+                .long("no-confirm")
+                .help("Start the workout without displaying its summary first")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("max_duration")
+                .long("max-duration")
+                .value_name("TIME")
+                .help("Stop the workout once it has lasted TIME, e.g. 10m, whatever is left of it")
+                .value_parser(parse_work_time)
+                .global(true),
+        )
+        .arg(
+            Arg::new("cap_excludes_pauses")
+                .long("cap-excludes-pauses")
+                .help("Do not count the pauses in --max-duration")
+                .action(ArgAction::SetTrue)
+                .requires("max_duration")
+                .global(true),
+        )
+        .arg(
+            Arg::new("trailing_rest")
+                .long("trailing-rest")
+                .help("Relax after the last rep too, instead of ending the workout with it")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("notify")
+                .long("notify")
+                .help("Send a desktop notification when the workout is complete")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("notify_each")
+                .long("notify-each")
+                .help("Send a desktop notification at the end of each rep")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("bell")
+                .long("bell")
+                .help("Ring the terminal bell twice when a rep starts, once when it ends, and 4 times at the end")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("sound")
+                .long("sound")
+                .value_name("PATH")
+                .help("Sound file (WAV, MP3, ...) played when a rep starts or ends")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("sound_start")
+                .long("sound-start")
+                .value_name("PATH")
+                .help("Sound file played when a rep starts, instead of --sound")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("sound_end")
+                .long("sound-end")
+                .value_name("PATH")
+                .help("Sound file played when a rep ends, instead of --sound")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("sound_done")
+                .long("sound-done")
+                .value_name("PATH")
+                .help("Sound file played once the whole workout is done")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("cadence")
+                .long("cadence")
+                .value_name("BPM")
+                .help("Ring the bell and flash the screen on the beat during the reps, up to 300 beats per minute")
+                .value_parser(value_parser!(u32).range(1..=MAX_CADENCE))
+                .global(true),
+        )
+        .arg(
+            Arg::new("halfway")
+                .long("halfway")
+                .help("Ring the bell when a rep is halfway through, e.g. to switch sides")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("get_ready")
+                .long("get-ready")
+                .help("Ring the bell each second at the end of the rests, to get ready without watching")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("speak")
+                .long("speak")
+                .help("Speak the label of each phase and its last 3 seconds (needs espeak or say)")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("log")
+                .long("log")
+                .value_name("FILE")
+                .help("Append the workout to a history file (JSON lines for .json or .jsonl, CSV otherwise)")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("big")
+                .long("big")
+                .help("Display the remaining time with large digits")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("no_help_line")
+                .long("no-help-line")
+                .help("Do not display the keys that can be pressed at the bottom of the screen")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("top_left")
+                .long("top-left")
+                .help("Display the messages from the upper left corner instead of centering them")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("truecolor")
+                .long("truecolor")
+                .help("Fade the color of the reps from green to red as the time runs out")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("show_next")
+                .long("show-next")
+                .help("Display what comes next during the relax, e.g. \"Next: Rep 3/5\"")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("palette")
+                .long("palette")
+                .help("Colors of the countdowns, \"colorblind\" also adds ▲ to the reps and ● to the rests")
+                .value_parser(["standard", "colorblind", "bright"])
+                .default_value("standard")
+                .global(true),
+        )
+        .arg(
+            Arg::new("bright")
+                .long("bright")
+                .help("Use the bright colors, same as --palette bright")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("palette")
+                .global(true),
+        )
+        .arg(
+            Arg::new("bold")
+                .long("bold")
+                .help("Display the label and the remaining time in bold, to read them from a distance")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("theme")
+                .long("theme")
+                .value_name("FILE")
+                .help("TOML file giving the color of each phase, e.g. rep = \"#ff8800\"")
+                .value_parser(|path: &str| load_theme(Path::new(path)))
+                .global(true),
+        )
+        .arg(
+            Arg::new("motivate")
+                .long("motivate")
+                .help("Display encouraging phrases in turn below the remaining time during the reps")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("phrases")
+                .long("phrases")
+                .value_name("FILE")
+                .help("File of the phrases of --motivate, one per line, instead of the built-in ones")
+                .value_parser(|path: &str| load_phrases(Path::new(path)))
+                .global(true),
+        )
+        .arg(
+            Arg::new("control_port")
+                .long("control-port")
+                .value_name("PORT")
+                .help("Accept POST /pause, /resume, /skip and /stop on localhost:PORT")
+                .value_parser(value_parser!(u16))
+                .global(true),
+        )
+        .arg(
+            Arg::new("socket")
+                .long("socket")
+                .value_name("PATH")
+                .help("Send the events of the workout as JSON to the clients of a Unix socket at PATH")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("on_phase")
+                .long("on-phase")
+                .value_name("COMMAND")
+                .help("Run the shell COMMAND at the start of each phase, e.g. 'notify-send \"$REPS_LABEL\"', with the phase and its index as $1 and $2")
+                .global(true),
+        )
+        .arg(
+            Arg::new("export")
+                .long("export")
+                .help("Print the workout as a short text that can be shared, and exit")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("dry_run")
+                .global(true),
+        )
+        .arg(
+            Arg::new("import")
+                .long("import")
+                .value_name("WORKOUT")
+                .help("Run the workout printed by --export")
+                .value_parser(Schedule::decode)
+                .conflicts_with_all(["num_reps", "rep_time", "relax_time", "config", "preset"]),
+        )
+        .arg(
+            Arg::new("plan")
+                .long("plan")
+                .value_name("FILE")
+                .help("Run the phases listed in the file, e.g. \"rep:30\" then \"rest:1m\" on the next line, - to read them from stdin")
+                .value_parser(|source: &str| load_plan(source))
+                .conflicts_with_all([
+                    "num_reps",
+                    "rep_time",
+                    "relax_time",
+                    "config",
+                    "preset",
+                    "import",
+                    "durations",
+                    "resume",
+                ]),
+        )
+        .arg(
+            Arg::new("pause_key")
+                .long("pause-key")
+                .value_name("KEY")
+                .help("Key pausing and resuming the countdown, e.g. \"p\" [default: space]")
+                .value_parser(parse_key)
+                .global(true),
+        )
+        .arg(
+            Arg::new("quit_key")
+                .long("quit-key")
+                .value_name("KEY")
+                .help("Key stopping the workout, along with Ctrl-C, e.g. \"q\" [default: esc]")
+                .value_parser(parse_key)
+                .global(true),
+        )
+        .arg(
+            Arg::new("skip_key")
+                .long("skip-key")
+                .value_name("KEY")
+                .help("Key skipping to the next phase [default: n]")
+                .value_parser(parse_key)
+                .global(true),
+        )
+        .arg(
+            Arg::new("restart_key")
+                .long("restart-key")
+                .value_name("KEY")
+                .help("Key starting the current rep again [default: r]")
+                .value_parser(parse_key)
+                .global(true),
+        )
+        .arg(
+            Arg::new("previous_key")
+                .long("previous-key")
+                .value_name("KEY")
+                .help("Key going back to the start of the previous phase [default: p]")
+                .value_parser(parse_key)
+                .global(true),
+        )
+        .arg(
+            Arg::new("ready_key")
+                .long("ready-key")
+                .value_name("KEY")
+                .help("Key ending the rest to go on with the next rep [default: enter]")
+                .value_parser(parse_key)
+                .global(true),
+        )
+        .arg(
+            Arg::new("precise")
+                .long("precise")
+                .help("Display the remaining time with tenths of a second, e.g. for short sprints")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("always_mmss")
+                .long("always-mmss")
+                .help("Display the remaining time as minutes and seconds, e.g. 0:05, even under a minute")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Print each countdown of the workout and the total duration, and exit")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print the events of the workout on stdout as JSON, one per line")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("quiet")
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .help("Update a single status line instead of the screen, without reading the keys")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("spinner")
+                .long("spinner")
+                .help("Animate a spinner next to the remaining time")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("flash")
+                .long("flash")
+                .help("Fill the screen with the color of each countdown for a moment as it starts, same as --cue-intensity strong")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("cue_intensity")
+                .global(true),
+        )
+        .arg(
+            Arg::new("cue_intensity")
+                .long("cue-intensity")
+                .help("How much the countdowns stand out: \"subtle\" turns the number red in the last seconds, \"strong\" also flashes the screen as they start")
+                .value_parser(["none", "subtle", "strong"])
+                .default_value("subtle")
+                .global(true),
+        )
+        .arg(
+            Arg::new("clock")
+                .long("clock")
+                .help("Display the time of day in the upper right corner of the screen")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["quiet", "json"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("tui")
+                .long("tui")
+                .help("Display a gauge under the countdown, and the list of the phases of the workout (drawn with termion)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["big", "quiet", "json"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .help("Write diagnostics on stderr, e.g. the keys received, to report an issue")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("set_title")
+                .long("set-title")
+                .help("Display the current countdown in the title of the terminal")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("speed")
+                .long("speed")
+                .value_name("FACTOR")
+                .help("Run the clock faster, e.g. 10 to run a 30s rep in 3s (for demos and tests)")
+                .value_parser(parse_speed)
+                .allow_negative_numbers(true)
+                .default_value("1")
+                .global(true),
+        )
+        .arg(
+            Arg::new("no_color")
+                .long("no-color")
+                .help("Do not use any color (also enabled by the NO_COLOR environment variable)")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .subcommand(
+            Command::new("tabata")
+                .about("Tabata workout: 8 rounds of 20s of work and 10s of rest by default")
+                .arg(
+                    Arg::new("rounds")
+                        .long("rounds")
+                        .help("Number of rounds")
+                        .display_order(0)
+                        .value_parser(value_parser!(u32).range(1..=MAX_COUNT))
+                        .default_value("8"),
+                )
+                .arg(
+                    Arg::new("work")
+                        .long("work")
+                        .help("Duration of the work in each round")
+                        .display_order(0)
+                        .value_parser(parse_work_time)
+                        .default_value("20"),
+                )
+                .arg(
+                    Arg::new("rest")
+                        .long("rest")
+                        .help("Duration of the rest after each round")
+                        .display_order(0)
+                        .value_parser(parse_time)
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("pomodoro")
+                .about("Pomodoro: 4 cycles of 25m of focus, each followed by a 5m break")
+                .arg(
+                    Arg::new("cycles")
+                        .long("cycles")
+                        .help("Number of cycles")
+                        .display_order(0)
+                        .value_parser(value_parser!(u32).range(1..=MAX_COUNT))
+                        .default_value("4"),
+                )
+                .arg(
+                    Arg::new("work")
+                        .long("work")
+                        .help("Duration of the focus in each cycle")
+                        .display_order(0)
+                        .value_parser(parse_work_time)
+                        .default_value("25m"),
+                )
+                .arg(
+                    Arg::new("break")
+                        .long("break")
+                        .help("Duration of the break after each cycle")
+                        .display_order(0)
+                        .value_parser(parse_time)
+                        .default_value("5m"),
+                )
+                .arg(
+                    Arg::new("long_break")
+                        .long("long-break")
+                        .help("Duration of the break after every few cycles")
+                        .display_order(0)
+                        .value_parser(parse_time)
+                        .default_value("15m"),
+                )
+                .arg(
+                    Arg::new("long_break_every")
+                        .long("long-break-every")
+                        .value_name("CYCLES")
+                        .help("Number of cycles between two long breaks")
+                        .display_order(0)
+                        .value_parser(value_parser!(u32).range(0..=MAX_COUNT))
+                        .default_value("4"),
+                ),
+        )
+        .subcommand(
+            Command::new("circuit")
+                .about("Circuit: go through a list of exercises, with a rest after each one, for a few rounds")
+                .arg(
+                    Arg::new("exercises")
+                        .long("exercises")
+                        .value_name("NAME:DURATION,...")
+                        .help("Exercises of the circuit, in order, e.g. \"squats:45,plank:1m\"")
+                        .display_order(0)
+                        .value_parser(parse_station)
+                        .value_delimiter(',')
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("rounds")
+                        .long("rounds")
+                        .help("Number of times the circuit is done")
+                        .display_order(0)
+                        .value_parser(value_parser!(u32).range(1..=MAX_COUNT))
+                        .default_value("3"),
+                )
+                .arg(
+                    Arg::new("rest")
+                        .long("rest")
+                        .help("Duration of the rest after each exercise")
+                        .display_order(0)
+                        .value_parser(parse_time)
+                        .default_value("15"),
+                ),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Do the last workout recorded with --log FILE again")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("History file written with --log")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Sum up the workouts recorded with --log FILE")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("History file written with --log")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("emom")
+                .about("Every minute on the minute: one countdown per minute, without rest")
+                .arg(
+                    Arg::new("minutes")
+                        .help("Total number of minutes")
+                        .value_parser(value_parser!(u32).range(1..=MAX_COUNT))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("stopwatch")
+                .about("Count the time up until ESC is pressed, e.g. for AMRAP workouts"),
+        )
+}
+
+/// Build the options shared by all the workouts, on top of the ones specific to a workout.
+fn common_options(matches: &ArgMatches, env: &EnvDefaults, opts: Options) -> Options {
+    let sound = matches.get_one::<PathBuf>("sound");
+    let sound_start = matches.get_one::<PathBuf>("sound_start").or(sound);
+    let sound_end = matches.get_one::<PathBuf>("sound_end").or(sound);
+    let key = |name| matches.get_one::<u8>(name).copied();
+    let defaults = Keys::default();
+    Options {
+        prep_time: env.get_duration(matches, "prep_time"),
+        sets: *matches.get_one::<u32>("sets").unwrap(),
+        set_rest: env.get_duration(matches, "set_rest"),
+        warmup: env.get_duration(matches, "warmup"),
+        cooldown: env.get_duration(matches, "cooldown"),
+        resumable: matches.get_flag("resumable"),
+        shuffle: matches.get_flag("shuffle"),
+        seed: matches.get_one::<u64>("seed").copied(),
+        rep_message: matches.get_one::<String>("rep_message").cloned(),
+        relax_message: matches.get_one::<String>("relax_message").cloned(),
+        confirm_exit: env.get_flag(matches, "confirm_exit"),
+        wait_for_key: matches.get_flag("wait_for_key"),
+        summary: !matches.get_flag("no_confirm"),
+        trailing_rest: matches.get_flag("trailing_rest"),
+        max_duration: matches.get_one::<u32>("max_duration").copied(),
+        cap_excludes_pauses: matches.get_flag("cap_excludes_pauses"),
+        notify: matches.get_flag("notify"),
+        notify_each: matches.get_flag("notify_each"),
+        bell: env.get_flag(matches, "bell"),
+        sound_start: sound_start.cloned(),
+        sound_end: sound_end.cloned(),
+        sound_done: matches.get_one::<PathBuf>("sound_done").cloned(),
+        halfway: matches.get_flag("halfway"),
+        get_ready: matches.get_flag("get_ready"),
+        speak: matches.get_flag("speak"),
+        cadence: matches.get_one::<u32>("cadence").copied().unwrap_or(0),
+        log: matches.get_one::<PathBuf>("log").cloned(),
+        big: env.get_flag(matches, "big"),
+        center: !matches.get_flag("top_left"),
+        help_line: !matches.get_flag("no_help_line"),
+        set_title: matches.get_flag("set_title"),
+        spinner: matches.get_flag("spinner"),
+        cue_intensity: match matches.get_one::<String>("cue_intensity").unwrap().as_str() {
+            _ if matches.get_flag("flash") => CueIntensity::Strong,
+            "none" => CueIntensity::None,
+            "strong" => CueIntensity::Strong,
+            _ => CueIntensity::Subtle,
+        },
+        tui: matches.get_flag("tui"),
+        time_of_day: matches.get_flag("clock"),
+        verbose: matches.get_flag("verbose"),
+        precise: matches.get_flag("precise"),
+        always_mmss: matches.get_flag("always_mmss"),
+        quiet: matches.get_flag("quiet"),
+        json: matches.get_flag("json"),
+        dry_run: matches.get_flag("dry_run"),
+        export: matches.get_flag("export"),
+        keys: Keys {
+            pause: key("pause_key").unwrap_or(defaults.pause),
+            quit: key("quit_key").unwrap_or(defaults.quit),
+            skip: key("skip_key").unwrap_or(defaults.skip),
+            restart: key("restart_key").unwrap_or(defaults.restart),
+            previous: key("previous_key").unwrap_or(defaults.previous),
+            ready: key("ready_key").unwrap_or(defaults.ready),
+        },
+        on_phase: matches.get_one::<String>("on_phase").cloned(),
+        control_port: matches.get_one::<u16>("control_port").copied(),
+        socket: matches.get_one::<PathBuf>("socket").cloned(),
+        show_next: matches.get_flag("show_next"),
+        palette: match matches.get_one::<String>("palette").unwrap().as_str() {
+            _ if matches.get_flag("bright") => Palette::Bright,
+            "colorblind" => Palette::Colorblind,
+            "bright" => Palette::Bright,
+            _ => Palette::Standard,
+        },
+        bold: matches.get_flag("bold"),
+        theme: matches
+            .get_one::<Theme>("theme")
+            .copied()
+            .unwrap_or_default(),
+        // Given phrases are meant to be displayed, even without --motivate
+        motivation: match matches.get_one::<Vec<String>>("phrases") {
+            Some(phrases) => phrases.clone(),
+            None if matches.get_flag("motivate") => {
+                MOTIVATION.iter().map(|phrase| phrase.to_string()).collect()
+            }
+            None => vec![],
+        },
+        fade: matches.get_flag("truecolor"),
+        // Smooth only if the terminal supports it, with the nearest ANSI colors otherwise
+        truecolor: env::var("COLORTERM")
+            .is_ok_and(|value| value == "truecolor" || value == "24bit"),
+        speed: *matches.get_one::<f64>("speed").unwrap(),
+        no_color: env.get_flag(matches, "no_color")
+            || env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+        ..opts
+    }
+}
+
+pub fn parse_args() -> Result<Options, clap::Error> {
+    let invalid = |e: String| clap::Error::raw(ErrorKind::InvalidValue, e + "\n");
+    let env = EnvDefaults::load().map_err(invalid)?;
+    let matches = command().try_get_matches()?;
+
+    if matches.get_flag("list_presets") {
+        for name in presets::list().map_err(invalid)? {
+            println!("{}", name);
+        }
+        exit(0);
+    }
+
+    let mut opts = match matches.subcommand() {
+        Some(("tabata", matches)) => common_options(
+            matches,
+            &env,
+            Options {
+                num_reps: *matches.get_one::<u32>("rounds").unwrap(),
+                rep_time: *matches.get_one::<u32>("work").unwrap(),
+                relax_time: *matches.get_one::<u32>("rest").unwrap(),
+                rep_label: Some("Round".to_string()),
+                ..Default::default()
+            },
+        ),
+        Some(("pomodoro", matches)) => {
+            let mut opts = common_options(
+                matches,
+                &env,
+                Options {
+                    num_reps: *matches.get_one::<u32>("cycles").unwrap(),
+                    rep_time: *matches.get_one::<u32>("work").unwrap(),
+                    relax_time: *matches.get_one::<u32>("break").unwrap(),
+                    long_break: *matches.get_one::<u32>("long_break").unwrap(),
+                    long_break_every: *matches.get_one::<u32>("long_break_every").unwrap(),
+                    rep_label: Some("Focus".to_string()),
+                    ..Default::default()
+                },
+            );
+            opts.relax_message
+                .get_or_insert_with(|| "Break".to_string());
+            opts
+        }
+        Some(("circuit", matches)) => {
+            let rest = *matches.get_one::<u32>("rest").unwrap();
+            let mut opts = common_options(
+                matches,
+                &env,
+                Options {
+                    exercises: matches
+                        .get_many::<(String, u32)>("exercises")
+                        .unwrap()
+                        .map(|(name, duration)| Exercise {
+                            name: name.clone(),
+                            reps: 1,
+                            rep_time: *duration,
+                            relax_time: rest,
+                        })
+                        .collect(),
+                    circuit: true,
+                    ..Default::default()
+                },
+            );
+            opts.sets = *matches.get_one::<u32>("rounds").unwrap();
+            // The rest after the last exercise is enough between two rounds
+            if matches.value_source("set_rest") != Some(ValueSource::CommandLine)
+                && env.duration("set_rest").is_none()
+            {
+                opts.set_rest = 0;
+            }
+            opts
+        }
+        Some(("replay", matches)) => {
+            let record = history::last(matches.get_one::<PathBuf>("file").unwrap());
+            let record = record.map_err(invalid)?;
+            if record.planned_reps == 0 {
+                return Err(invalid("Nothing to replay: no rep was planned".to_string()));
+            }
+            if record.rep_time == 0 {
+                return Err(invalid(
+                    "Nothing to replay: the duration of the reps was not recorded".to_string(),
+                ));
+            }
+            common_options(
+                matches,
+                &env,
+                Options {
+                    // All the reps planned, even if the workout was stopped early
+                    num_reps: record.planned_reps,
+                    rep_time: record.rep_time,
+                    relax_time: record.relax_time,
+                    ..Default::default()
+                },
+            )
+        }
+        Some(("stats", matches)) => {
+            let records = history::all(matches.get_one::<PathBuf>("file").unwrap());
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs());
+            print!("{}", history::stats(&records.map_err(invalid)?, now));
+            exit(0);
+        }
+        Some(("emom", matches)) => common_options(
+            matches,
+            &env,
+            Options {
+                mode: Mode::Emom,
+                num_reps: *matches.get_one::<u32>("minutes").unwrap(),
+                rep_time: 60,
+                ..Default::default()
+            },
+        ),
+        Some(("stopwatch", matches)) => common_options(
+            matches,
+            &env,
+            Options {
+                mode: Mode::Stopwatch,
+                ..Default::default()
+            },
+        ),
+        _ if matches.get_flag("resume") => {
+            let checkpoint = resume::load().map_err(invalid)?;
+            let saved = match checkpoint.options(false) {
+                // Most likely given up rather than interrupted
+                Ok(_) if checkpoint.age() > resume::STALE_AFTER => {
+                    let hours = checkpoint.age() / 3600;
+                    let age = match hours {
+                        0..=47 => format!("{} hours", hours),
+                        _ => format!("{} days", hours / 24),
+                    };
+                    checkpoint.options(ask(&format!(
+                        "The workout was interrupted {} ago, start it again from the beginning?",
+                        age
+                    ))?)
+                }
+                Ok(opts) => Ok(opts),
+                Err(e) if ask(&format!("{}, start it again from the beginning?", e))? => {
+                    checkpoint.options(true)
+                }
+                Err(e) => Err(e),
+            };
+            Options {
+                truecolor: env::var("COLORTERM")
+                    .is_ok_and(|value| value == "truecolor" || value == "24bit"),
+                ..saved.and_then(check_saved).map_err(invalid)?
+            }
+        }
+        _ if matches.contains_id("preset") || matches.get_flag("repeat_last") => {
+            let saved = match matches.get_one::<String>("preset") {
+                Some(name) => presets::load(name),
+                None => presets::load_last(),
+            };
+            Options {
+                // The terminal may not be the one where the workout was saved
+                truecolor: env::var("COLORTERM")
+                    .is_ok_and(|value| value == "truecolor" || value == "24bit"),
+                dry_run: matches.get_flag("dry_run"),
+                export: matches.get_flag("export"),
+                ..saved.and_then(check_saved).map_err(invalid)?
+            }
+        }
+        _ if matches.contains_id("import") || matches.contains_id("plan") => common_options(
+            &matches,
+            &env,
+            Options {
+                schedule: matches
+                    .get_one::<Schedule>("import")
+                    .or_else(|| matches.get_one::<Schedule>("plan"))
+                    .cloned(),
+                ..Default::default()
+            },
+        ),
+        _ => match matches.get_one::<PathBuf>("config") {
+            Some(path) => common_options(
+                &matches,
+                &env,
+                Options {
+                    exercises: load_exercises(path).map_err(invalid)?,
+                    ..Default::default()
+                },
+            ),
+            None => {
+                // Both given by the list of durations otherwise
+                let (num_reps, rep_time) = match matches.get_many::<u32>("durations") {
+                    Some(mut durations) => (0, *durations.next().unwrap()),
+                    None => match matches.get_many::<u32>("until_stop") {
+                        Some(mut durations) => {
+                            let rep_time = *durations.next().unwrap();
+                            // The relax of 0s is fine, unlike the reps
+                            let text = matches.get_raw("until_stop").unwrap().next().unwrap();
+                            check_duration(&text.to_string_lossy(), rep_time, true)
+                                .map_err(|e| invalid(format!("Invalid --until-stop: {}", e)))?;
+                            (0, rep_time)
+                        }
+                        None => (
+                            *matches.get_one::<u32>("num_reps").unwrap(),
+                            *matches.get_one::<u32>("rep_time").unwrap(),
+                        ),
+                    },
+                };
+                let rep_times = rep_times(&matches, num_reps, rep_time);
+                let relaxes: Vec<u32> = matches
+                    .get_many::<u32>("relaxes")
+                    .unwrap_or_default()
+                    .copied()
+                    .collect();
+                if relaxes.len() > 1 && relaxes.len() != rep_times.len() {
+                    return Err(invalid(format!(
+                        "--relaxes has {} durations for {} reps, give either one per rep or a \
+                         single one for all of them",
+                        relaxes.len(),
+                        rep_times.len()
+                    )));
+                }
+                let relax_time = match relaxes[..] {
+                    [relax_time] => relax_time,
+                    _ => matches
+                        .get_one::<u32>("relax_time")
+                        .or_else(|| matches.get_many::<u32>("until_stop")?.nth(1))
+                        .copied()
+                        .or_else(|| env.duration("relax_time"))
+                        .unwrap_or(0),
+                };
+                common_options(
+                    &matches,
+                    &env,
+                    Options {
+                        num_reps: if rep_times.is_empty() {
+                            num_reps
+                        } else {
+                            rep_times.len() as u32
+                        },
+                        rep_time,
+                        rep_times,
+                        relax_time,
+                        relax_times: if relaxes.len() > 1 { relaxes } else { vec![] },
+                        relax_range: matches.get_one::<(u32, u32)>("relax_range").copied(),
+                        names: matches
+                            .get_many::<String>("names")
+                            .unwrap_or_default()
+                            .cloned()
+                            .collect(),
+                        ..Default::default()
+                    },
+                )
+            }
+        },
+    };
+    if let Some(&rounds) = matches.get_one::<u32>("workout_rounds") {
+        opts.sets = rounds;
+        opts.set_label = Some("Round".to_string());
+    }
+    check_keys(&opts.keys).map_err(invalid)?;
+    if let Some(name) = matches.get_one::<String>("save") {
+        presets::save(name, &opts).map_err(invalid)?;
+    }
+    Ok(opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(args: &[&str]) -> Result<ArgMatches, clap::Error> {
+        command().try_get_matches_from([&["reps"], args].concat())
+    }
+
+    #[test]
+    fn until_stop_takes_the_durations() {
+        let durations = |args: &[&str]| -> Vec<u32> {
+            matches(args)
+                .unwrap()
+                .get_many::<u32>("until_stop")
+                .unwrap()
+                .copied()
+                .collect()
+        };
+        assert_eq!(durations(&["--until-stop", "30"]), [30]);
+        assert_eq!(durations(&["--until-stop", "1m", "10"]), [60, 10]);
+        assert_eq!(
+            durations(&["--dry-run", "--until-stop", "30", "0"]),
+            [30, 0]
+        );
+        // There is no number of reps to give
+        let error = matches(&["--until-stop", "30", "10", "5"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+        let error = matches(&["5", "--until-stop", "30"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn command_line_over_environment() {
+        let env = EnvDefaults {
+            durations: vec![("prep_time", 10)],
+            flags: vec![("bell", true), ("big", false)],
+        };
+        let get = |args: &[&str]| {
+            let matches = matches(args).unwrap();
+            let flags = (
+                env.get_flag(&matches, "bell"),
+                env.get_flag(&matches, "big"),
+            );
+            (env.get_duration(&matches, "prep_time"), flags)
+        };
+        assert_eq!(get(&["5", "30"]), (10, (true, false)));
+        assert_eq!(get(&["5", "30", "--prep", "0", "--big"]), (0, (true, true)));
+        // Without the environment, the built-in defaults
+        let matches = matches(&["5", "30"]).unwrap();
+        let env = EnvDefaults::default();
+        assert_eq!(env.get_duration(&matches, "prep_time"), 3);
+        assert!(!env.get_flag(&matches, "bell"));
+    }
+}
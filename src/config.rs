@@ -0,0 +1,182 @@
+//! Workouts described in a TOML file, e.g.
+//!
+//! ```toml
+//! [[exercise]]
+//! name = "Push-ups"
+//! reps = 10
+//! rep_time = "30s"
+//! relax_time = 10
+//! ```
+//!
+//! and themes giving the color of each phase, e.g.
+//!
+//! ```toml
+//! prep = "#5f87ff"
+//! rep = "#ff5f5f"
+//! relax = "#87d787"
+//! ```
+//!
+//! The phrases of the motivation are in a plain text file instead, one per line, as well as the
+//! plans listing the phases of a workout.
+
+use std::fs;
+use std::io::{stdin, Read};
+use std::path::Path;
+
+use reps::{check_duration, parse_duration, Exercise, Rgb, Schedule, Theme};
+use serde::Deserialize;
+
+/// A duration may be given in seconds, or in any format accepted by `parse_duration`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationValue {
+    Seconds(u32),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExerciseConfig {
+    name: String,
+    reps: u32,
+    rep_time: DurationValue,
+    relax_time: DurationValue,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    exercise: Vec<ExerciseConfig>,
+}
+
+/// Seconds of the duration, checked like the ones given on the command line.
+fn to_seconds(value: &DurationValue, work: bool) -> Result<u32, String> {
+    match value {
+        DurationValue::Seconds(seconds) => check_duration(&seconds.to_string(), *seconds, work),
+        DurationValue::Text(text) => check_duration(text, parse_duration(text)?, work),
+    }
+}
+
+/// Load the list of exercises from the file.
+pub fn load_exercises(path: &Path) -> Result<Vec<Exercise>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    // The error reports the line and column of the issue
+    let config: Config = toml::from_str(&content)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e.to_string().trim_end()))?;
+
+    if config.exercise.is_empty() {
+        return Err(format!(
+            "Invalid {}: no exercise found, add at least one [[exercise]]",
+            path.display()
+        ));
+    }
+    config
+        .exercise
+        .iter()
+        .map(|exercise| {
+            let invalid = |e| format!("Invalid {}: {}: {}", path.display(), exercise.name, e);
+            // Nothing would be done otherwise
+            if exercise.reps == 0 {
+                return Err(invalid("reps must be at least 1".to_string()));
+            }
+            let rep_time = to_seconds(&exercise.rep_time, true).map_err(invalid)?;
+            Ok(Exercise {
+                name: exercise.name.clone(),
+                reps: exercise.reps,
+                rep_time,
+                relax_time: to_seconds(&exercise.relax_time, false).map_err(invalid)?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeConfig {
+    prep: Option<String>,
+    warmup: Option<String>,
+    rep: Option<String>,
+    relax: Option<String>,
+    set_rest: Option<String>,
+    cooldown: Option<String>,
+}
+
+/// Parse a color written in hexadecimal, e.g. "#ff8800" or "#f80".
+fn parse_color(s: &str) -> Result<Rgb, String> {
+    let invalid = || format!("\"{}\" is not a color, e.g. \"#ff8800\"", s);
+    let digits = s.strip_prefix('#').ok_or_else(invalid)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let component = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+    match digits.len() {
+        // Each digit is repeated, e.g. "#f80" is "#ff8800"
+        3 => {
+            let digit = |i: usize| component(&digits[i..=i]).map(|value| value * 17);
+            Ok((digit(0)?, digit(1)?, digit(2)?))
+        }
+        6 => Ok((
+            component(&digits[0..2])?,
+            component(&digits[2..4])?,
+            component(&digits[4..6])?,
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+/// Load the colors of the phases from the file.
+pub fn load_theme(path: &Path) -> Result<Theme, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let config: ThemeConfig = toml::from_str(&content)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e.to_string().trim_end()))?;
+    let color = |phase: &str, value: &Option<String>| {
+        value
+            .as_deref()
+            .map(parse_color)
+            .transpose()
+            .map_err(|e| format!("Invalid {}: {}: {}", path.display(), phase, e))
+    };
+    Ok(Theme {
+        prep: color("prep", &config.prep)?,
+        warmup: color("warmup", &config.warmup)?,
+        rep: color("rep", &config.rep)?,
+        relax: color("relax", &config.relax)?,
+        set_rest: color("set_rest", &config.set_rest)?,
+        cooldown: color("cooldown", &config.cooldown)?,
+    })
+}
+
+/// Load the phrases of the motivation from the file, one per line. The empty lines, and the ones
+/// starting with '#', are ignored.
+pub fn load_phrases(path: &Path) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let phrases: Vec<String> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    if phrases.is_empty() {
+        return Err(format!("Invalid {}: no phrase found", path.display()));
+    }
+    Ok(phrases)
+}
+
+/// Load the plan of the workout from the file, or from stdin if `source` is "-", e.g. to pipe it
+/// from a script.
+pub fn load_plan(source: &str) -> Result<Schedule, String> {
+    let content = if source == "-" {
+        let mut content = String::new();
+        stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| format!("Could not read the plan from stdin: {}", e))?;
+        content
+    } else {
+        fs::read_to_string(source).map_err(|e| format!("Could not read {}: {}", source, e))?
+    };
+    Schedule::from_plan(&content)
+}
@@ -0,0 +1,132 @@
+//! Remote control of the workout over HTTP, e.g. from a phone:
+//!
+//! ```sh
+//! curl -X POST localhost:9000/pause
+//! ```
+//!
+//! The requests are turned into the keys that the user would press, and read with the keyboard.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use reps::Keys;
+use tiny_http::{Method, Response, Server};
+
+/// Start the server on a background thread, listening on localhost only. `paused` tells whether
+/// the workout is paused, so that pausing it twice does not resume it. The requests are turned
+/// into the `keys` chosen by the user.
+pub fn serve(
+    port: u16,
+    paused: Arc<AtomicBool>,
+    keys: Keys,
+) -> Result<Receiver<io::Result<u8>>, String> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("Could not listen on port {}: {}", port, e))?;
+    Ok(handle(server, paused, keys))
+}
+
+/// Turn the requests received by the `server` into keys, on a background thread.
+fn handle(server: Server, paused: Arc<AtomicBool>, keys: Keys) -> Receiver<io::Result<u8>> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            if *request.method() != Method::Post {
+                let _ = request.respond(Response::from_string("Use POST\n").with_status_code(405));
+                continue;
+            }
+            let key = match request.url() {
+                "/pause" if !paused.swap(true, Ordering::Relaxed) => Some(Ok(keys.pause)),
+                "/resume" if paused.swap(false, Ordering::Relaxed) => Some(Ok(keys.pause)),
+                // Already in the requested state
+                "/pause" | "/resume" => None,
+                "/skip" => Some(Ok(keys.skip)),
+                // Without asking for a confirmation, nobody may be in front of the screen
+                "/stop" => Some(Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "stopped remotely",
+                ))),
+                _ => {
+                    let response = Response::from_string(
+                        "Unknown command, use /pause, /resume, /skip or /stop\n",
+                    );
+                    let _ = request.respond(response.with_status_code(404));
+                    continue;
+                }
+            };
+            if let Some(key) = key {
+                // The workout is over
+                if sender.send(key).is_err() {
+                    break;
+                }
+            }
+            let _ = request.respond(Response::from_string("ok\n"));
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    /// Server listening on a free port, and that port.
+    fn server(paused: bool) -> (Receiver<io::Result<u8>>, u16) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let paused = Arc::new(AtomicBool::new(paused));
+        (handle(server, paused, Keys::default()), port)
+    }
+
+    /// Status code of the response to the request with `method` on `path`.
+    fn request(port: u16, method: &str, path: &str) -> u16 {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            method, path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response[9..12].parse().unwrap()
+    }
+
+    #[test]
+    fn requests_are_keys() {
+        let keys = Keys::default();
+        let (receiver, port) = server(false);
+        assert_eq!(request(port, "POST", "/pause"), 200);
+        assert_eq!(receiver.recv().unwrap().unwrap(), keys.pause);
+        assert_eq!(request(port, "POST", "/skip"), 200);
+        assert_eq!(receiver.recv().unwrap().unwrap(), keys.skip);
+        assert_eq!(request(port, "POST", "/resume"), 200);
+        assert_eq!(receiver.recv().unwrap().unwrap(), keys.pause);
+        assert_eq!(request(port, "POST", "/stop"), 200);
+        let error = receiver.recv().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn already_paused() {
+        let keys = Keys::default();
+        let (receiver, port) = server(true);
+        assert_eq!(request(port, "POST", "/pause"), 200);
+        assert_eq!(request(port, "POST", "/resume"), 200);
+        // The pause was ignored, the workout is resumed
+        assert_eq!(receiver.recv().unwrap().unwrap(), keys.pause);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn unknown_requests() {
+        let (receiver, port) = server(false);
+        assert_eq!(request(port, "POST", "/rewind"), 404);
+        assert_eq!(request(port, "GET", "/pause"), 405);
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,94 @@
+//! Parsing and formatting of durations.
+
+/// Longest countdown accepted, a day: longer ones are most likely a mistake
+pub const MAX_DURATION: u32 = 24 * 60 * 60;
+
+/// Check a duration of `seconds` written `text` by the user: it must not be longer than
+/// `MAX_DURATION`, nor 0 if it is the duration of some `work`, e.g. a rep.
+pub fn check_duration(text: &str, seconds: u32, work: bool) -> Result<u32, String> {
+    if seconds > MAX_DURATION {
+        return Err(format!("\"{}\" is longer than a day", text));
+    }
+    if work && seconds == 0 {
+        return Err(format!(
+            "\"{}\" is not a duration of work, it must be at least 1s",
+            text
+        ));
+    }
+    Ok(seconds)
+}
+
+/// Parse a duration provided by the user and return it in seconds.
+/// The accepted formats are plain seconds ("90"), a combination of units in decreasing order
+/// ("1h", "2m", "1m30s", "90s") or a clock-like format ("1:30", "1:00:00").
+pub fn parse_duration(s: &str) -> Result<u32, String> {
+    let invalid = || {
+        format!(
+            "'{}' is not a valid duration (expected e.g. 90, 90s, 1m30s or 1:30)",
+            s
+        )
+    };
+
+    if let Ok(seconds) = s.parse() {
+        return Ok(seconds);
+    }
+
+    let mut total: u32 = 0;
+    if s.contains(':') {
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.len() > 3 {
+            return Err(invalid());
+        }
+        for (i, part) in parts.iter().enumerate() {
+            let value: u32 = part.parse().map_err(|_| invalid())?;
+            if i > 0 && value >= 60 {
+                return Err(invalid());
+            }
+            total = total
+                .checked_mul(60)
+                .and_then(|t| t.checked_add(value))
+                .ok_or_else(invalid)?;
+        }
+        return Ok(total);
+    }
+
+    let mut number = String::new();
+    let mut previous_multiplier = u32::MAX;
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let multiplier = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        // Every unit must be preceded by a number, and appear only once in decreasing order
+        if number.is_empty() || multiplier >= previous_multiplier {
+            return Err(invalid());
+        }
+        let value: u32 = number.parse().map_err(|_| invalid())?;
+        total = value
+            .checked_mul(multiplier)
+            .and_then(|v| total.checked_add(v))
+            .ok_or_else(invalid)?;
+        number.clear();
+        previous_multiplier = multiplier;
+    }
+    if !number.is_empty() || previous_multiplier == u32::MAX {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// Format a number of seconds as "m:ss", or "h:mm:ss" for an hour or more.
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
@@ -0,0 +1,54 @@
+//! Why a workout stopped before the end.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum RepsError {
+    /// The user asked to stop, e.g. with ESC
+    UserExit,
+    /// The workout lasted as long as allowed by `max_duration`
+    TimeCap,
+    /// The keys could not be read, or the program was asked to stop, e.g. by a signal
+    Io(io::Error),
+}
+
+impl fmt::Display for RepsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepsError::UserExit => write!(f, "Exiting"),
+            RepsError::TimeCap => write!(f, "Time cap reached"),
+            RepsError::Io(e) => write!(f, "Error: {}", e),
+        }
+    }
+}
+
+impl error::Error for RepsError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RepsError::UserExit | RepsError::TimeCap => None,
+            RepsError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for RepsError {
+    fn from(e: io::Error) -> RepsError {
+        RepsError::Io(e)
+    }
+}
+
+/// `io::Error` cannot be compared, two of them are equal when they have the same kind and message
+impl PartialEq for RepsError {
+    fn eq(&self, other: &RepsError) -> bool {
+        match (self, other) {
+            (RepsError::UserExit, RepsError::UserExit) => true,
+            (RepsError::TimeCap, RepsError::TimeCap) => true,
+            (RepsError::Io(a), RepsError::Io(b)) => {
+                a.kind() == b.kind() && a.to_string() == b.to_string()
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Events of the workout as JSON, one object per line, printed on stdout or sent to a socket,
+//! e.g.
+//!
+//! ```json
+//! {"timestamp":1700000000000,"event":"start","phase":"rep","index":1,"label":"Rep 1/5","duration":30}
+//! {"timestamp":1700000000001,"event":"tick","label":"Rep 1/5","elapsed":0,"remaining":30}
+//! ```
+
+use std::io::{stdout, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::json::quote;
+use reps::{Event, RepsError};
+
+/// Line of the event, made of the fields already formatted as JSON, with the current time.
+fn line(event: &str, fields: &[(&str, String)]) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |timestamp| timestamp.as_millis());
+    let mut line = format!("{{\"timestamp\":{},\"event\":{}", timestamp, quote(event));
+    for (name, value) in fields {
+        line.push_str(&format!(",{}:{}", quote(name), value));
+    }
+    line.push('}');
+    line
+}
+
+/// Print the line of an event on stdout.
+pub fn print(line: &str) {
+    // Flushed right away, for the programs reading the events as they happen
+    let mut stdout = stdout();
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+/// Line of the end of the workout, with the number of reps done and its duration in seconds.
+pub fn end(result: &Result<(), RepsError>, reps: u32, duration: u64) -> String {
+    let mut fields = vec![
+        ("completed", result.is_ok().to_string()),
+        ("reps", reps.to_string()),
+        ("duration", duration.to_string()),
+    ];
+    if let Err(error) = result {
+        fields.push(("error", quote(&error.to_string())));
+    }
+    line("end", &fields)
+}
+
+/// Line of the event.
+pub fn event(event: &Event) -> String {
+    match *event {
+        Event::Start {
+            phase,
+            index,
+            label,
+            duration,
+        } => line(
+            "start",
+            &[
+                ("phase", quote(phase)),
+                ("index", index.to_string()),
+                ("label", quote(label)),
+                ("duration", duration.to_string()),
+            ],
+        ),
+        Event::Tick {
+            label,
+            elapsed,
+            remaining,
+        } => line(
+            "tick",
+            &[
+                ("label", quote(label)),
+                ("elapsed", elapsed.to_string()),
+                (
+                    "remaining",
+                    remaining.map_or("null".to_string(), |remaining| remaining.to_string()),
+                ),
+            ],
+        ),
+        Event::Pause => line("pause", &[]),
+        Event::Resume => line("resume", &[]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_object;
+
+    /// Fields of the line, without its timestamp
+    fn fields(line: &str) -> Vec<(String, String)> {
+        let mut fields = parse_object(line).unwrap();
+        assert_eq!(fields.remove(0).0, "timestamp");
+        fields
+    }
+
+    #[test]
+    fn quoted_labels() {
+        let label = "Say \"hi\"\\ to C:\\reps\n\tnow\u{1} ✓";
+        let line = event(&Event::Start {
+            phase: "rep",
+            index: 1,
+            label,
+            duration: 30,
+        });
+        assert!(
+            line.contains(r#""label":"Say \"hi\"\\ to C:\\reps\n\tnow\u0001 ✓","duration":30}"#)
+        );
+        let field = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(
+            fields(&line),
+            [
+                field("event", "start"),
+                field("phase", "rep"),
+                field("index", "1"),
+                field("label", label),
+                field("duration", "30"),
+            ]
+        );
+
+        let line = event(&Event::Tick {
+            label: "\"",
+            elapsed: 0,
+            remaining: None,
+        });
+        assert!(line.ends_with(r#","event":"tick","label":"\"","elapsed":0,"remaining":null}"#));
+    }
+}
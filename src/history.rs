@@ -0,0 +1,520 @@
+//! History of the workouts. Each workout is appended to a file as a JSON object on its own line
+//! if the extension of the file is `.json` or `.jsonl`, or as a line of CSV otherwise. The last
+//! workout can be read back, to do it again, and all of them summed up.
+
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::mem::take;
+use std::path::Path;
+
+use crate::json;
+use crate::local_time;
+use reps::format_duration;
+
+/// Entry of the history
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    /// Start of the workout, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Reps done
+    pub reps: u32,
+    /// Reps planned, more than the reps done if the workout was stopped early
+    pub planned_reps: u32,
+    pub rep_time: u32,
+    pub relax_time: u32,
+    /// Duration of the workout, in seconds
+    pub duration: u64,
+    /// Whether the workout was done until the end, or stopped by the user
+    pub completed: bool,
+    /// Names of the exercises, e.g. "Push-ups, Squats"
+    pub exercise: String,
+}
+
+/// Columns of a CSV file, in the order they are written
+const CSV_COLUMNS: [&str; 8] = [
+    "timestamp",
+    "reps",
+    "rep_time",
+    "relax_time",
+    "duration",
+    "completed",
+    "exercise",
+    "planned_reps",
+];
+/// Columns added since the first version of the history, which may be missing from a file
+const NEW_COLUMNS: [&str; 2] = ["exercise", "planned_reps"];
+
+fn is_json(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("json") | Some("jsonl")
+    )
+}
+
+/// Field of a CSV file, quoted if needed: the quotes inside are doubled.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Rows of a CSV file, each made of its fields. A quoted field may contain commas, quotes and
+/// line breaks.
+fn csv_rows(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    let (mut row, mut field) = (vec![], String::new());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                        Some('"') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("a quoted field is not closed".to_string()),
+                    }
+                }
+                if !matches!(chars.peek(), Some(',' | '\r' | '\n') | None) {
+                    return Err(format!("\"{}\" is followed by more than a comma", field));
+                }
+            }
+            ',' => row.push(take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(take(&mut field));
+                rows.push(take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    // Blank lines
+    rows.retain(|row| row.len() > 1 || !row[0].trim().is_empty());
+    Ok(rows)
+}
+
+impl Record {
+    fn to_csv(&self) -> String {
+        [
+            self.timestamp.to_string(),
+            self.reps.to_string(),
+            self.rep_time.to_string(),
+            self.relax_time.to_string(),
+            self.duration.to_string(),
+            self.completed.to_string(),
+            csv_field(&self.exercise),
+            self.planned_reps.to_string(),
+        ]
+        .join(",")
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            concat!(
+                "{{\"timestamp\":{},\"reps\":{},\"rep_time\":{},",
+                "\"relax_time\":{},\"duration\":{},\"completed\":{},\"exercise\":{},",
+                "\"planned_reps\":{}}}"
+            ),
+            self.timestamp,
+            self.reps,
+            self.rep_time,
+            self.relax_time,
+            self.duration,
+            self.completed,
+            json::quote(&self.exercise),
+            self.planned_reps
+        )
+    }
+
+    /// Build a record from the value of each of its fields.
+    fn from_fields<'a>(field: impl Fn(&str) -> Option<&'a str>) -> Result<Record, String> {
+        fn parse<T: std::str::FromStr>(value: Option<&str>, name: &str) -> Result<T, String> {
+            let value = value.ok_or_else(|| format!("the {} is missing", name))?;
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("\"{}\" is not a valid {}", value, name))
+        }
+        let reps = parse(field("reps"), "reps")?;
+        Ok(Record {
+            timestamp: parse(field("timestamp"), "timestamp")?,
+            reps,
+            // Not recorded by the previous versions
+            planned_reps: match field("planned_reps") {
+                Some(value) => parse(Some(value), "planned_reps")?,
+                None => reps,
+            },
+            rep_time: parse(field("rep_time"), "rep_time")?,
+            relax_time: parse(field("relax_time"), "relax_time")?,
+            duration: parse(field("duration"), "duration")?,
+            completed: parse(field("completed"), "completed")?,
+            exercise: field("exercise").unwrap_or_default().to_string(),
+        })
+    }
+
+    /// Parse a line written by `to_json`. Unknown fields are ignored.
+    fn from_json(line: &str) -> Result<Record, String> {
+        let fields = json::parse_object(line)?;
+        Record::from_fields(|name| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.as_str())
+        })
+    }
+}
+
+/// Append the record to the history file, creating it if needed. A CSV file starts with a header.
+pub fn append(path: &Path, record: &Record) -> Result<(), String> {
+    let warning = |e: std::io::Error| {
+        format!(
+            "Warning: could not write the workout to {}: {}",
+            path.display(),
+            e
+        )
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(warning)?;
+    let mut lines = String::new();
+    if is_json(path) {
+        lines.push_str(&record.to_json());
+    } else {
+        if file.metadata().map_err(warning)?.len() == 0 {
+            lines.push_str(&CSV_COLUMNS.join(","));
+            lines.push('\n');
+        }
+        lines.push_str(&record.to_csv());
+    }
+    lines.push('\n');
+    file.write_all(lines.as_bytes()).map_err(warning)
+}
+
+/// Workouts recorded in the content of the history file. The columns of a CSV file are given by
+/// its header, which must have all the columns written by `append`, apart from the newest ones:
+/// the columns of another file cannot be trusted.
+fn records(path: &Path, content: &str) -> Result<Vec<Record>, String> {
+    if is_json(path) {
+        return content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Record::from_json)
+            .collect();
+    }
+    let rows = csv_rows(content)?;
+    let Some((header, rows)) = rows.split_first() else {
+        return Ok(vec![]);
+    };
+    let missing = CSV_COLUMNS
+        .iter()
+        .filter(|column| !NEW_COLUMNS.contains(column))
+        .any(|column| !header.iter().any(|name| name == column));
+    if missing {
+        return Err(format!(
+            "the columns are \"{}\" instead of \"{}\", it was written by another version of \
+             reps",
+            header.join(","),
+            CSV_COLUMNS.join(",")
+        ));
+    }
+    rows.iter()
+        .map(|row| {
+            Record::from_fields(|name| {
+                let column = header.iter().position(|column| column == name)?;
+                row.get(column).map(String::as_str)
+            })
+        })
+        .collect()
+}
+
+/// Read the last workout recorded in the history file.
+pub fn last(path: &Path) -> Result<Record, String> {
+    let invalid = |e: String| format!("Invalid {}: {}", path.display(), e);
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    records(path, &content)
+        .map_err(invalid)?
+        .pop()
+        .ok_or_else(|| invalid("no workout recorded".to_string()))
+}
+
+/// Read all the workouts recorded in the history file, none if it does not exist yet.
+pub fn all(path: &Path) -> Result<Vec<Record>, String> {
+    let invalid = |e: String| format!("Invalid {}: {}", path.display(), e);
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("Could not read {}: {}", path.display(), e)),
+    };
+    records(path, &content).map_err(invalid)
+}
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Number of consecutive days with a workout, up to today. The streak is not broken until the end
+/// of the day, so it may end yesterday.
+fn streak(days: &BTreeSet<u64>, today: u64) -> u64 {
+    let last = if days.contains(&today) {
+        today
+    } else {
+        today.saturating_sub(1)
+    };
+    (0..=last)
+        .rev()
+        .take_while(|day| days.contains(day))
+        .count() as u64
+}
+
+/// Day of the timestamp, from 1970-01-01, in the time zone of the system: a workout late in the
+/// evening belongs to that day, wherever UTC already is.
+fn local_day(timestamp: u64) -> u64 {
+    let offset = local_time::utc_offset(timestamp as i64).unwrap_or(0);
+    (timestamp as i64 + offset)
+        .div_euclid(SECONDS_PER_DAY as i64)
+        .max(0) as u64
+}
+
+/// Table of the number of workouts and of the time spent in them, this week and in total, along
+/// with the current daily streak. `now` is in seconds since the Unix epoch.
+pub fn stats(records: &[Record], now: u64) -> String {
+    table(records, now, local_day)
+}
+
+/// Same as `stats`, with the `day` of each timestamp
+fn table(records: &[Record], now: u64, day: impl Fn(u64) -> u64) -> String {
+    let today = day(now);
+    // The weeks start on Monday, and 1970-01-01 was a Thursday
+    let monday = today - (today + 3) % 7;
+    let week: Vec<&Record> = records
+        .iter()
+        .filter(|record| day(record.timestamp) >= monday)
+        .collect();
+    let days: BTreeSet<u64> = records.iter().map(|record| day(record.timestamp)).collect();
+    let time = |records: &mut dyn Iterator<Item = &Record>| {
+        format_duration(records.map(|record| record.duration).sum())
+    };
+    let streak = streak(&days, today);
+    format!(
+        "{:<10} {:>8} {:>9}\n{:<10} {:>8} {:>9}\n{:<10} {:>8} {:>9}\n\nCurrent streak: {} day{}\n",
+        "",
+        "Sessions",
+        "Time",
+        "This week",
+        week.len(),
+        time(&mut week.iter().copied()),
+        "Total",
+        records.len(),
+        time(&mut records.iter()),
+        streak,
+        if streak == 1 { "" } else { "s" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01, a Monday
+    const MONDAY: u64 = 19723 * SECONDS_PER_DAY;
+    const HOUR: u64 = 60 * 60;
+
+    fn record(timestamp: u64) -> Record {
+        Record {
+            timestamp,
+            reps: 5,
+            planned_reps: 5,
+            rep_time: 30,
+            relax_time: 10,
+            duration: 200,
+            completed: true,
+            exercise: "Rep".to_string(),
+        }
+    }
+
+    fn utc(timestamp: u64) -> u64 {
+        timestamp / SECONDS_PER_DAY
+    }
+
+    /// Sessions of the week, sessions in total and streak
+    fn numbers(table: &str) -> (&str, &str, &str) {
+        let lines: Vec<&str> = table.lines().collect();
+        let week = lines[1]
+            .trim_start_matches("This week")
+            .split_whitespace()
+            .next()
+            .unwrap();
+        let total = lines[2]
+            .trim_start_matches("Total")
+            .split_whitespace()
+            .next()
+            .unwrap();
+        let streak = lines[4]
+            .trim_start_matches("Current streak: ")
+            .split(' ')
+            .next()
+            .unwrap();
+        (week, total, streak)
+    }
+
+    /// Names that must be quoted in CSV, escaped in JSON, or both
+    const AWKWARD_NAMES: [&str; 7] = [
+        "Push-ups, wide",
+        "The \"good\" squats",
+        "Plank\non the elbows",
+        "Back\\slash",
+        "\ttab and\r\nCRLF",
+        "Épaule 💪",
+        "",
+    ];
+
+    fn named(exercise: &str) -> Record {
+        Record {
+            exercise: exercise.to_string(),
+            ..record(MONDAY)
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut content = format!("{}\n", CSV_COLUMNS.join(","));
+        for name in AWKWARD_NAMES {
+            content.push_str(&named(name).to_csv());
+            content.push('\n');
+        }
+        let expected: Vec<Record> = AWKWARD_NAMES.iter().map(|name| named(name)).collect();
+        assert_eq!(records(Path::new("history.csv"), &content), Ok(expected));
+        assert_eq!(
+            named("Push-ups, \"wide\"").to_csv(),
+            "1704067200,5,30,10,200,true,\"Push-ups, \"\"wide\"\"\",5"
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let content: String = AWKWARD_NAMES
+            .iter()
+            .map(|name| format!("{}\n", named(name).to_json()))
+            .collect();
+        let expected: Vec<Record> = AWKWARD_NAMES.iter().map(|name| named(name)).collect();
+        assert_eq!(records(Path::new("history.jsonl"), &content), Ok(expected));
+        assert_eq!(
+            named("Plank\n\"low\"").to_json(),
+            "{\"timestamp\":1704067200,\"reps\":5,\"rep_time\":30,\"relax_time\":10,\
+             \"duration\":200,\"completed\":true,\"exercise\":\"Plank\\n\\\"low\\\"\",\
+             \"planned_reps\":5}"
+        );
+    }
+
+    #[test]
+    fn files_of_the_previous_versions() {
+        let csv =
+            "timestamp,reps,rep_time,relax_time,duration,completed\n1704067200,5,30,10,200,true\n";
+        let json = "{\"timestamp\":1704067200,\"reps\":5,\"rep_time\":30,\"relax_time\":10,\
+                    \"duration\":200,\"completed\":true}\n";
+        let expected = Ok(vec![named("")]);
+        assert_eq!(records(Path::new("history.csv"), csv), expected);
+        assert_eq!(records(Path::new("history.json"), json), expected);
+        assert!(records(Path::new("history.csv"), "reps,name\n5,Plank\n")
+            .unwrap_err()
+            .contains("it was written by another version of reps"));
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let csv = |content: &str| records(Path::new("history.csv"), content).unwrap_err();
+        let header = CSV_COLUMNS.join(",");
+        assert_eq!(
+            csv(&format!(
+                "{}\n1704067200,5,30,10,200,true,\"Plank\n",
+                header
+            )),
+            "a quoted field is not closed"
+        );
+        assert_eq!(
+            csv(&format!("{}\n1704067200,5,30,10,200,maybe,Plank\n", header)),
+            "\"maybe\" is not a valid completed"
+        );
+        let json = |content: &str| records(Path::new("history.json"), content).unwrap_err();
+        assert_eq!(json("[1, 2]"), "it is not a JSON object");
+        assert_eq!(json("{\"timestamp\":1,"), "it is not a JSON object");
+        assert_eq!(json("{\"timestamp\":1}"), "the reps is missing");
+    }
+
+    #[test]
+    fn append_and_read_back() {
+        for extension in ["csv", "jsonl"] {
+            let path = std::env::temp_dir().join(format!(
+                "reps-history-{}.{}",
+                std::process::id(),
+                extension
+            ));
+            let _ = fs::remove_file(&path);
+            for name in AWKWARD_NAMES {
+                append(&path, &named(name)).unwrap();
+            }
+            let expected: Vec<Record> = AWKWARD_NAMES.iter().map(|name| named(name)).collect();
+            assert_eq!(all(&path), Ok(expected));
+            assert_eq!(last(&path), Ok(named("")));
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn empty_history() {
+        assert_eq!(
+            table(&[], MONDAY, utc),
+            "           Sessions      Time\n\
+             This week         0      0:00\n\
+             Total             0      0:00\n\
+             \n\
+             Current streak: 0 days\n"
+        );
+    }
+
+    #[test]
+    fn week_starts_on_monday() {
+        let records = [record(MONDAY - HOUR), record(MONDAY + HOUR)];
+        let wednesday = MONDAY + 2 * SECONDS_PER_DAY + 12 * HOUR;
+        assert_eq!(numbers(&table(&records, wednesday, utc)), ("1", "2", "0"));
+        // Still the previous week where it was 23:00 on Sunday
+        let records = [record(MONDAY + HOUR)];
+        let new_york = |timestamp: u64| (timestamp - 5 * HOUR) / SECONDS_PER_DAY;
+        assert_eq!(numbers(&table(&records, wednesday, utc)).0, "1");
+        assert_eq!(numbers(&table(&records, wednesday, new_york)).0, "0");
+    }
+
+    #[test]
+    fn streak_ending_yesterday() {
+        let records = [
+            record(MONDAY + 8 * HOUR),
+            record(MONDAY + SECONDS_PER_DAY + 20 * HOUR),
+        ];
+        // Not broken until the end of the day
+        let wednesday = MONDAY + 2 * SECONDS_PER_DAY + 12 * HOUR;
+        assert_eq!(numbers(&table(&records, wednesday, utc)).2, "2");
+        let thursday = wednesday + SECONDS_PER_DAY;
+        assert_eq!(numbers(&table(&records, thursday, utc)).2, "0");
+    }
+
+    #[test]
+    fn broken_streak() {
+        let records = [
+            record(MONDAY - SECONDS_PER_DAY),
+            record(MONDAY + SECONDS_PER_DAY),
+            record(MONDAY + 2 * SECONDS_PER_DAY),
+        ];
+        let wednesday = MONDAY + 2 * SECONDS_PER_DAY + 12 * HOUR;
+        let table = table(&records, wednesday, utc);
+        assert_eq!(numbers(&table), ("2", "3", "2"));
+        assert!(table.ends_with("Current streak: 2 days\n"));
+    }
+}
@@ -0,0 +1,45 @@
+//! User command run at the start of each phase, e.g. to turn a light red during the reps.
+
+use std::process::{Child, Command, Stdio};
+
+pub struct Hook {
+    command: String,
+    /// Commands still running
+    children: Vec<Child>,
+}
+
+impl Hook {
+    pub fn new(command: &str) -> Hook {
+        Hook {
+            command: command.to_string(),
+            children: vec![],
+        }
+    }
+
+    /// Start the command in the background, run by the shell so that it may have arguments of its
+    /// own. It gets the kind of phase and its position as `$1` and `$2`, and in the environment
+    /// along with the label: `REPS_PHASE`, `REPS_INDEX`, `REPS_LABEL`.
+    pub fn run(&mut self, phase: &str, index: u32, label: &str) -> Result<(), String> {
+        // Reap the previous commands once they are over
+        self.children
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            // $0 of the command
+            .arg("reps")
+            .arg(phase)
+            .arg(index.to_string())
+            .env("REPS_PHASE", phase)
+            .env("REPS_INDEX", index.to_string())
+            .env("REPS_LABEL", label)
+            // The screen belongs to the workout
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Warning: could not run {}: {}", self.command, e))?;
+        self.children.push(child);
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! The little JSON needed by the history and the events: strings, and objects whose values are
+//! all strings, numbers, booleans or null.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Quote the string as a JSON string.
+pub fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn skip_spaces(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// Four hexadecimal digits of a `\u` escape
+fn code_unit(chars: &mut Peekable<Chars>) -> Option<u32> {
+    let digits: String = chars.take(4).collect();
+    if digits.len() != 4 {
+        return None;
+    }
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+/// Read a string, from its opening quote to its closing one, and unescape it.
+fn string(chars: &mut Peekable<Chars>) -> Option<String> {
+    if chars.next() != Some('"') {
+        return None;
+    }
+    let mut text = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(text),
+            '\\' => text.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                '/' => '/',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let high = code_unit(chars)?;
+                    // Outside of the basic plane, the character is written as a surrogate pair
+                    let code = if (0xd800..0xdc00).contains(&high) {
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        let low = code_unit(chars)?;
+                        if !(0xdc00..0xe000).contains(&low) {
+                            return None;
+                        }
+                        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                    } else {
+                        high
+                    };
+                    char::from_u32(code)?
+                }
+                _ => return None,
+            }),
+            c if c.is_control() => return None,
+            c => text.push(c),
+        }
+    }
+}
+
+/// Fields of a JSON object, in order. The strings are unquoted, and the other values are kept as
+/// they are written, e.g. "30" or "true". Objects and arrays are not supported as values.
+pub fn parse_object(text: &str) -> Result<Vec<(String, String)>, String> {
+    let invalid = || "it is not a JSON object".to_string();
+    let mut chars = text.chars().peekable();
+    skip_spaces(&mut chars);
+    if chars.next() != Some('{') {
+        return Err(invalid());
+    }
+    let mut fields = vec![];
+    skip_spaces(&mut chars);
+    if chars.next_if_eq(&'}').is_none() {
+        loop {
+            skip_spaces(&mut chars);
+            let name = string(&mut chars).ok_or_else(invalid)?;
+            skip_spaces(&mut chars);
+            if chars.next() != Some(':') {
+                return Err(invalid());
+            }
+            skip_spaces(&mut chars);
+            let value = if chars.peek() == Some(&'"') {
+                string(&mut chars).ok_or_else(invalid)?
+            } else {
+                let mut value = String::new();
+                while let Some(c) =
+                    chars.next_if(|c| c.is_ascii_alphanumeric() || "+-.".contains(*c))
+                {
+                    value.push(c);
+                }
+                if value.is_empty() {
+                    return Err(invalid());
+                }
+                value
+            };
+            fields.push((name, value));
+            skip_spaces(&mut chars);
+            match chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(invalid()),
+            }
+        }
+    }
+    skip_spaces(&mut chars);
+    match chars.next() {
+        None => Ok(fields),
+        Some(_) => Err(invalid()),
+    }
+}
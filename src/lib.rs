@@ -0,0 +1,2455 @@
+extern crate rand;
+extern crate serde;
+extern crate termion;
+
+mod big;
+mod duration;
+mod error;
+mod schedule;
+mod terminal;
+mod tui;
+
+pub use duration::{check_duration, format_duration, parse_duration, MAX_DURATION};
+pub use error::RepsError;
+pub use schedule::{Phase, Schedule};
+pub use terminal::{RawMode, TerminalGuard};
+
+use std::cell::Cell;
+use std::fmt;
+use std::io::{Bytes, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use termion::color;
+use termion::color::Color;
+use termion::style;
+
+const DURATION_1_SECOND: Duration = Duration::from_millis(1000);
+/// How often the user input is checked while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Below this width, the progress bar is drawn with plain ASCII characters
+const MIN_BLOCK_BAR_WIDTH: u16 = 20;
+/// Most reps displayed as dots, e.g. "●●●○○", they are counted as "Reps 3/5" above
+const MAX_REP_DOTS: u32 = 20;
+/// Time added or removed from the countdown with '+' and '-'
+const ADJUSTMENT_SECONDS: i64 = 10;
+/// Time between two consecutive bells, so that they can be told apart
+const BELL_INTERVAL: Duration = Duration::from_millis(200);
+/// Silences between the bells rung once the whole workout is done: three quick ones, then a last
+/// one after a longer silence, so that it cannot be mistaken for the end of a rep
+const FANFARE: [Duration; 3] = [
+    Duration::from_millis(120),
+    Duration::from_millis(120),
+    Duration::from_millis(500),
+];
+/// Number of seconds highlighted (and spoken, if enabled) at the end of each countdown
+const FINAL_SECONDS: u32 = 3;
+/// Frames of the spinner displayed next to the remaining time
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+/// Delay after which a second of a countdown that ended late is logged
+const LATE_SECOND: Duration = Duration::from_millis(10);
+/// Time the screen is filled with the color of a countdown starting, with `flash`
+const FLASH_DURATION: Duration = Duration::from_millis(150);
+/// Time the summary of the workout is displayed before it starts, unless a key is pressed
+pub const SUMMARY_DURATION: Duration = Duration::from_secs(3);
+/// Size of the display as (columns, rows) when the one of the terminal is not known
+pub const DEFAULT_SIZE: (u16, u16) = (80, 24);
+/// Phrases displayed during the reps with `--motivate`, unless others are given
+pub const MOTIVATION: [&str; 8] = [
+    "Push!",
+    "Keep going!",
+    "You've got this!",
+    "Stay strong!",
+    "Breathe",
+    "Nice form!",
+    "Almost there!",
+    "One more!",
+];
+/// Time each phrase of the motivation is displayed, before the next one
+const MOTIVATION_SECONDS: u32 = 5;
+/// Time between two frames of the spinner
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+/// Color of the reps in the colorblind palette
+const ORANGE: color::AnsiValue = color::AnsiValue(208);
+
+/// Source of the keys pressed by the user.
+pub trait Input {
+    /// Return the next key pressed by the user, or None if no key is available right now. It
+    /// must never block. An error of kind `Interrupted` stops the workout as if the user pressed
+    /// ESC. The errors of kind `WouldBlock` or `TimedOut` are transient, the keys are read again
+    /// later. Any other error stops it as a failure.
+    fn next_key(&mut self) -> Option<std::io::Result<u8>>;
+}
+
+impl<R: Read> Input for Bytes<R> {
+    fn next_key(&mut self) -> Option<std::io::Result<u8>> {
+        self.next()
+    }
+}
+
+/// Size of the terminal given by `size`, e.g. by `termion::terminal_size`, or `DEFAULT_SIZE`
+/// along with a warning telling why when it could not be determined. A terminal reported
+/// without any column or row is not trusted either, nothing could be drawn on it.
+pub fn screen_size(size: std::io::Result<(u16, u16)>) -> ((u16, u16), Option<String>) {
+    let reason = match size {
+        Ok((width, height)) if width > 0 && height > 0 => return ((width, height), None),
+        Ok((width, height)) => format!("it has {} columns and {} rows", width, height),
+        Err(e) => e.to_string(),
+    };
+    let warning = format!(
+        "Warning: the size of the terminal is unknown ({}), the screen was drawn for {}x{}",
+        reason, DEFAULT_SIZE.0, DEFAULT_SIZE.1
+    );
+    (DEFAULT_SIZE, Some(warning))
+}
+
+/// Destination of the messages displayed to the user.
+pub trait Output {
+    /// Display a single message, replacing whatever was displayed before.
+    fn render(&mut self, message: &str);
+
+    /// Size of the display as (columns, rows).
+    fn size(&self) -> (u16, u16) {
+        DEFAULT_SIZE
+    }
+
+    /// Notify the user outside of the main display, when a rep is over.
+    fn notify(&mut self, _message: &str) {}
+
+    /// Ring the terminal bell once.
+    fn bell(&mut self) {}
+
+    /// Start playing the sound file in the background. It must never block.
+    fn play_sound(&mut self, _path: &Path) {}
+
+    /// Start speaking the text in the background. It must never block.
+    fn speak(&mut self, _text: &str) {}
+
+    /// Set the title of the window, e.g. to display the current countdown.
+    fn set_title(&mut self, _title: &str) {}
+
+    /// Whether the display was resized since the last call, so that the screen must be drawn
+    /// again.
+    fn resized(&mut self) -> bool {
+        false
+    }
+
+    /// Report what happens during the workout, e.g. to another program.
+    fn event(&mut self, _event: &Event) {}
+
+    /// Write a diagnostic of the workout, e.g. a second that ended late. It is only displayed
+    /// when the user asked for it.
+    fn log(&mut self, _message: &str) {}
+}
+
+/// Something that happens during the workout
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Event<'a> {
+    /// A countdown starts, or starts again when the rep is restarted
+    Start {
+        /// Kind of countdown, e.g. "rep" or "relax"
+        phase: &'a str,
+        /// Position of the countdown in the workout, from 1
+        index: u32,
+        label: &'a str,
+        duration: u32,
+    },
+    /// Sent every second
+    Tick {
+        label: &'a str,
+        /// Seconds elapsed since the start of the countdown
+        elapsed: u32,
+        /// Seconds remaining, none for the stopwatch
+        remaining: Option<u32>,
+    },
+    Pause,
+    Resume,
+}
+
+/// Source of time, used to measure and wait for the duration of the countdowns.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+
+    /// Sleep until the `deadline`, or not at all if it is already past.
+    fn sleep_until(&self, deadline: Instant) {
+        self.sleep(deadline.saturating_duration_since(self.now()))
+    }
+}
+
+/// Clock backed by the system time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// Clock backed by the system time, running `speed` times faster (or slower, below 1).
+pub struct ScaledClock {
+    start: Instant,
+    speed: f64,
+}
+
+impl ScaledClock {
+    pub fn new(speed: f64) -> ScaledClock {
+        ScaledClock {
+            start: Instant::now(),
+            speed,
+        }
+    }
+}
+
+impl Clock for ScaledClock {
+    fn now(&self) -> Instant {
+        self.start + self.start.elapsed().mul_f64(self.speed)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration.div_f64(self.speed))
+    }
+}
+
+/// Everything the timer needs to interact with the outside world.
+pub struct Io<'a> {
+    pub input: &'a mut dyn Input,
+    pub output: &'a mut dyn Output,
+    pub clock: &'a dyn Clock,
+}
+
+/// Kind of workout
+#[derive(Default, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Mode {
+    /// Reps, with a rest after each of them
+    #[default]
+    Reps,
+    /// Every minute on the minute: one rep per minute, without any rest
+    Emom,
+    /// Time counting up until the user stops it
+    Stopwatch,
+}
+
+/// Colors of the countdowns
+#[derive(Default, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Palette {
+    /// Red for the reps, green for the relax
+    #[default]
+    Standard,
+    /// Orange for the reps, blue for the relax, which can be told apart with the most common
+    /// color vision deficiencies. The labels also start with a symbol: ▲ for the reps and ● for
+    /// the rests
+    Colorblind,
+    /// Bright variants of the standard colors, for the terminals displaying them too dim to be
+    /// read from a distance
+    Bright,
+}
+
+/// How much the countdowns stand out as they start and end
+#[derive(Default, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CueIntensity {
+    /// The countdowns keep the same style until the end, and the beats do not flash
+    None,
+    /// The number turns red in the last seconds
+    #[default]
+    Subtle,
+    /// The screen is also filled with the color of each countdown for a moment as it starts
+    Strong,
+}
+
+/// Color given as its red, green and blue components
+pub type Rgb = (u8, u8, u8);
+
+/// Colors of the phases chosen by the user, instead of the ones of the palette
+#[derive(Default, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub prep: Option<Rgb>,
+    pub warmup: Option<Rgb>,
+    pub rep: Option<Rgb>,
+    pub relax: Option<Rgb>,
+    pub set_rest: Option<Rgb>,
+    pub cooldown: Option<Rgb>,
+}
+
+/// A named exercise, and its own reps
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Exercise {
+    pub name: String,
+    pub reps: u32,
+    pub rep_time: u32,
+    pub relax_time: u32,
+}
+
+/// Options of a workout. They can be saved to, and loaded from, TOML: missing fields keep their
+/// default value
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Options {
+    pub mode: Mode,
+    pub num_reps: u32,
+    pub rep_time: u32,
+    pub relax_time: u32,
+    pub prep_time: u32,
+    /// Send a notification when the workout is complete
+    pub notify: bool,
+    /// Send a notification at the end of each rep
+    pub notify_each: bool,
+    /// Ring the bell when a rep starts (twice) or ends (once), and when the workout is done
+    pub bell: bool,
+    /// Sound played when a rep starts
+    pub sound_start: Option<PathBuf>,
+    /// Sound played when a rep ends
+    pub sound_end: Option<PathBuf>,
+    /// Sound played once the whole workout is done
+    pub sound_done: Option<PathBuf>,
+    /// Display the remaining time with large digits, in the middle of the screen
+    pub big: bool,
+    /// Do not use any color
+    pub no_color: bool,
+    /// Port on localhost where the workout can be controlled over HTTP, disabled if not set
+    pub control_port: Option<u16>,
+    /// Unix domain socket where the events of the workout are sent as JSON, disabled if not set
+    pub socket: Option<PathBuf>,
+    /// Command run at the start of each phase, with the kind of phase and its position
+    pub on_phase: Option<String>,
+    /// Print the plan of the workout instead of running it
+    #[serde(skip)]
+    pub dry_run: bool,
+    /// Print the schedule of the workout, encoded to be shared, instead of running it
+    #[serde(skip)]
+    pub export: bool,
+    /// Schedule of the workout, e.g. shared by someone else, instead of the one planned from the
+    /// other options
+    pub schedule: Option<Schedule>,
+    /// Report the events on the output, e.g. as JSON, instead of drawing the countdowns
+    pub json: bool,
+    /// Display a single line with the label and the remaining time, instead of the whole screen
+    pub quiet: bool,
+    /// Colors of the countdowns
+    pub palette: Palette,
+    /// Display the label and the remaining time of the countdowns in bold, unless colors are
+    /// disabled
+    pub bold: bool,
+    /// Colors of the countdowns replacing the ones of the palette, with 24-bit colors if the
+    /// terminal supports them and the nearest of the 256 colors otherwise
+    pub theme: Theme,
+    /// Phrases displayed in turn below the remaining time during the reps, e.g. "Push!",
+    /// disabled if empty
+    pub motivation: Vec<String>,
+    /// Keys pressed to control the countdowns
+    pub keys: Keys,
+    /// Name of a rep in the labels, "Rep" by default
+    pub rep_label: Option<String>,
+    /// Exercises to do one after the other. When empty, a single exercise is built from
+    /// `num_reps`, `rep_time` and `relax_time`
+    pub exercises: Vec<Exercise>,
+    /// Names displayed instead of "Rep N/M", one per rep
+    pub names: Vec<String>,
+    /// Message displayed instead of "Rep N/M", where "{n}" and "{total}" are replaced with the
+    /// number of the rep and the number of reps
+    pub rep_message: Option<String>,
+    /// Message displayed instead of "Relax!"
+    pub relax_message: Option<String>,
+    /// Number of times all the exercises are repeated, 1 if not set
+    pub sets: u32,
+    /// Rest between two sets
+    pub set_rest: u32,
+    /// Label of the sets, e.g. "Round" for "Round 1/3", "Set" if not set
+    pub set_label: Option<String>,
+    /// Go through the exercises once per set, which is then called a round, e.g.
+    /// "Squats – Round 2/3"
+    pub circuit: bool,
+    /// Relax after every `long_break_every` reps, instead of `relax_time`, e.g. in a pomodoro
+    pub long_break: u32,
+    /// Number of reps between two long breaks, disabled if 0
+    pub long_break_every: u32,
+    /// Ask for a confirmation before exiting with ESC or Ctrl-C
+    pub confirm_exit: bool,
+    /// Wait for a key to be pressed before starting the workout, e.g. to get into position
+    pub wait_for_key: bool,
+    /// Display the summary of the workout before it starts, to check what was planned
+    pub summary: bool,
+    /// Relax after the last rep as after the others, instead of ending the workout with it
+    pub trailing_rest: bool,
+    /// Stop the workout once it has lasted this many seconds, whatever is left of it, e.g. to do
+    /// as many reps as possible in 10 minutes
+    pub max_duration: Option<u32>,
+    /// Do not count the pauses in `max_duration`
+    pub cap_excludes_pauses: bool,
+    /// Duration of the warmup before the first rep, skipped if 0
+    pub warmup: u32,
+    /// Duration of the cooldown after the last rep, skipped if 0
+    pub cooldown: u32,
+    /// File where the workout is recorded once it is over
+    pub log: Option<PathBuf>,
+    /// Beats per minute ticked during the reps, disabled if 0
+    pub cadence: u32,
+    /// Speak the label of each countdown and its last seconds
+    pub speak: bool,
+    /// Ring the bell and display a note when a rep is halfway through
+    pub halfway: bool,
+    /// Ring the bell once a second at the end of the rests, to get ready for the next rep
+    pub get_ready: bool,
+    /// Center the messages on the screen instead of displaying them from the upper left
+    pub center: bool,
+    /// Display the keys that can be pressed on the last line of the screen
+    pub help_line: bool,
+    /// Display the current countdown in the title of the terminal
+    pub set_title: bool,
+    /// Animate a spinner next to the remaining time
+    pub spinner: bool,
+    /// How much the countdowns stand out as they start and end
+    pub cue_intensity: CueIntensity,
+    /// Display the countdowns above a gauge and the list of the phases of the workout
+    pub tui: bool,
+    /// Display the time of day in the upper right corner of the screen
+    pub time_of_day: bool,
+    /// Write diagnostics of the workout on stderr, e.g. the keys received
+    pub verbose: bool,
+    /// Display the remaining time with tenths of a second, e.g. "4.3s", except with large digits
+    pub precise: bool,
+    /// Display the remaining time as minutes and seconds, e.g. "0:05", even in the countdowns of a
+    /// minute or less
+    pub always_mmss: bool,
+    /// Display what comes next during the relax, e.g. "Next: Rep 3/5"
+    pub show_next: bool,
+    /// Fade the color of the reps from green to red as the time runs out
+    pub fade: bool,
+    /// The terminal supports 24-bit colors, for a smooth fade
+    pub truecolor: bool,
+    /// Bounds of the duration of each relax, picked at random instead of `relax_time`
+    pub relax_range: Option<(u32, u32)>,
+    /// Save the position in the workout as it goes, so that it can be resumed once interrupted
+    pub resumable: bool,
+    /// Phase of the schedule where the workout starts (from 0), and the seconds remaining in it,
+    /// to go on with a workout that was interrupted
+    #[serde(skip)]
+    pub resume_at: Option<(u32, u32)>,
+    /// Go through the exercises in a random order, the same one in each set
+    pub shuffle: bool,
+    /// Seed of the random choices, e.g. the order of the exercises, to make the same ones again
+    pub seed: Option<u64>,
+    /// Duration of each rep, instead of `rep_time`. The planned duration is then displayed in the
+    /// label, as it changes from one rep to the other
+    pub rep_times: Vec<u32>,
+    /// Duration of the relax after each rep, instead of `relax_time`
+    pub relax_times: Vec<u32>,
+    /// How much faster than the real time the clock runs, e.g. 10 to run a 30s rep in 3s.
+    /// 1 if not set
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+}
+
+/// Speed of the clock of the saved workouts that do not set it: the real time
+fn default_speed() -> f64 {
+    1.0
+}
+
+impl Options {
+    /// List of all the exercises of the workout
+    pub fn exercises(&self) -> Vec<Exercise> {
+        if !self.exercises.is_empty() {
+            return self.exercises.clone();
+        }
+        vec![Exercise {
+            name: self.rep_label.clone().unwrap_or_else(|| "Rep".to_string()),
+            reps: self.num_reps,
+            rep_time: self.rep_time,
+            relax_time: self.relax_time,
+        }]
+    }
+
+    /// Whether the workout makes any sound, which can then be muted
+    pub fn has_sound(&self) -> bool {
+        self.bell
+            || self.halfway
+            || self.get_ready
+            || self.speak
+            || self.cadence > 0
+            || self.sound_start.is_some()
+            || self.sound_end.is_some()
+            || self.sound_done.is_some()
+    }
+
+    /// Whether the reps go on until the user stops them, when 0 reps are requested
+    pub fn until_stop(&self) -> bool {
+        self.mode == Mode::Reps
+            && self.schedule.is_none()
+            && self.exercises.is_empty()
+            && self.num_reps == 0
+    }
+}
+
+/// Action requested by the user with a key
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Action {
+    Pause,
+    /// Go to the next phase ('n')
+    Skip,
+    /// Add or remove seconds to the countdown ('+' or '-')
+    Adjust(i64),
+    /// Start the current rep again ('r')
+    Restart,
+    /// Go back to the previous phase ('p')
+    Previous,
+    /// End the rest, the user being ready for the next rep (Enter)
+    Ready,
+    /// Stop the program, once the user confirmed it (ESC or Ctrl-C with `confirm_exit`)
+    Quit,
+    /// Display the keys that can be pressed ('?')
+    Help,
+    /// Turn the sounds off, or on again ('m')
+    Mute,
+}
+
+/// Kind of screen where the keys are read, which decides the actions available
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Controls {
+    /// Only pausing and quitting
+    Stopwatch,
+    /// Also skipping the countdown and adjusting its time
+    Countdown,
+    /// Also ending the rest once the user is ready
+    Rest,
+    /// Also starting the rep again
+    Rep,
+}
+
+/// How a countdown ended
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Completed,
+    /// The user requested to go to the next phase
+    Skipped,
+    /// The user requested to start the countdown again
+    Restarted,
+    /// The user requested to go back to the previous phase
+    Previous,
+}
+
+/// Summary of a workout
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Stats {
+    /// Number of reps that ran until the end of their countdown
+    pub reps: u32,
+    /// Time spent in the reps, without the pauses
+    pub work_time: Duration,
+    /// Time spent relaxing between the reps and the sets, without the pauses
+    pub rest_time: Duration,
+    /// Time since the start of the workout, including the pauses
+    pub duration: Duration,
+    pub pauses: u32,
+}
+
+/// Keys pressed to control the countdowns. Ctrl-C always stops the program as well.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keys {
+    /// Pause or resume the countdown, space by default
+    pub pause: u8,
+    /// Stop the program, ESC by default
+    pub quit: u8,
+    /// Go to the next phase, 'n' by default
+    pub skip: u8,
+    /// Start the current rep again, 'r' by default
+    pub restart: u8,
+    /// Go back to the start of the previous phase, 'p' by default
+    pub previous: u8,
+    /// End the rest and go on with the next rep, Enter by default
+    pub ready: u8,
+}
+
+impl Default for Keys {
+    fn default() -> Keys {
+        Keys {
+            pause: b' ',
+            quit: 27,
+            skip: b'n',
+            restart: b'r',
+            previous: b'p',
+            ready: b'\r',
+        }
+    }
+}
+
+impl Keys {
+    /// Name of the key, as displayed to the user
+    pub fn name(key: u8) -> String {
+        match key {
+            27 => "ESC".to_string(),
+            b' ' => "SPACE".to_string(),
+            b'\t' => "TAB".to_string(),
+            b'\r' => "ENTER".to_string(),
+            key => (key as char).to_string(),
+        }
+    }
+}
+
+/// Consume all the keys from the input. Is in charge of detecting if the user request to
+/// the program (the `quit` key or Ctrl-C).
+///
+/// # Returns
+/// - The keys pressed since the last call, in order (empty if no keys were pressed)
+/// - If a key was pressed that should stop the program (`quit`, Ctrl-C), Err(UserExit), even if
+///   other keys were pressed before. If `confirm_exit` is set, they are returned with the other
+///   keys instead, so that the user can confirm it first
+/// - If the keys could not be read, Err(Io), unless the error is transient: the keys pressed
+///   until then are returned, the next ones are read with the next call
+fn consume_all_keystrokes(
+    input: &mut dyn Input,
+    quit: u8,
+    confirm_exit: bool,
+) -> Result<Vec<u8>, RepsError> {
+    let mut keys = vec![];
+
+    loop {
+        match input.next_key() {
+            Some(e) => match e {
+                Ok(key) if (key == quit || key == 3) && !confirm_exit => {
+                    return Err(RepsError::UserExit); // Ctrl-C as well
+                }
+                // Stopped from elsewhere, e.g. with a remote control
+                Err(e) if e.kind() == ErrorKind::Interrupted => return Err(RepsError::UserExit),
+                // Nothing to read yet, the workout must not stop for it
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return Ok(keys)
+                }
+                Err(e) => return Err(RepsError::Io(e)),
+
+                Ok(key) => keys.push(key),
+            },
+            None => return Ok(keys),
+        }
+    }
+}
+
+/// Whether the user requested to stop the program with these keys
+fn is_quit(keys: &[u8], quit: u8) -> bool {
+    keys.contains(&quit) || keys.contains(&3)
+}
+
+/// Ask the user to confirm that the program should stop.
+///
+/// # Returns
+/// - Err(UserExit) if the user confirmed with 'y'
+/// - Ok(()) if the user pressed any other key, the screen must then be redrawn
+fn confirm_quit(io: &mut Io, opts: &Options) -> Result<(), RepsError> {
+    io.output.render(&layout(io, opts, "Quit? (y/n)"));
+    loop {
+        match consume_all_keystrokes(io.input, opts.keys.quit, true)?.first() {
+            Some(b'y') => return Err(RepsError::UserExit),
+            Some(_) => return Ok(()),
+            None => io.clock.sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+/// Find the action requested by the user with these keys. When several actions are requested
+/// at once, quitting has the priority, then skipping, restarting, adjusting the time, pausing and
+/// going back, then displaying the help and muting. The other keys are ignored.
+fn action(keys: &[u8], bindings: &Keys) -> Option<Action> {
+    if is_quit(keys, bindings.quit) {
+        return Some(Action::Quit);
+    }
+    if keys.contains(&bindings.skip) {
+        return Some(Action::Skip);
+    }
+    if keys.contains(&bindings.restart) {
+        return Some(Action::Restart);
+    }
+    let adjustment: i64 = keys
+        .iter()
+        .map(|key| match key {
+            b'+' => ADJUSTMENT_SECONDS,
+            b'-' => -ADJUSTMENT_SECONDS,
+            _ => 0,
+        })
+        .sum();
+    if adjustment != 0 {
+        return Some(Action::Adjust(adjustment));
+    }
+    if keys.contains(&bindings.pause) {
+        return Some(Action::Pause);
+    }
+    if keys.contains(&bindings.previous) {
+        return Some(Action::Previous);
+    }
+    if keys.contains(&bindings.ready) {
+        return Some(Action::Ready);
+    }
+    if keys.contains(&b'?') {
+        return Some(Action::Help);
+    }
+    if keys.contains(&b'm') {
+        return Some(Action::Mute);
+    }
+    None
+}
+
+/// Keys that can be pressed, and what they do
+fn bindings(opts: &Options, controls: Controls) -> Vec<(String, String)> {
+    let keys = &opts.keys;
+    let mut bindings = vec![(Keys::name(keys.pause), "pause".to_string())];
+    if controls != Controls::Stopwatch {
+        bindings.push((Keys::name(keys.skip), "skip".to_string()));
+        bindings.push((Keys::name(keys.previous), "back".to_string()));
+        bindings.push((
+            "+/-".to_string(),
+            format!("add/remove {}s", ADJUSTMENT_SECONDS),
+        ));
+    }
+    if controls == Controls::Rest {
+        bindings.push((Keys::name(keys.ready), "ready".to_string()));
+    }
+    if controls == Controls::Rep {
+        bindings.push((Keys::name(keys.restart), "restart".to_string()));
+    }
+    if controls != Controls::Stopwatch && opts.has_sound() {
+        bindings.push(("m".to_string(), "mute".to_string()));
+    }
+    bindings.push(("?".to_string(), "help".to_string()));
+    bindings.push((
+        Keys::name(keys.quit),
+        if opts.confirm_exit {
+            "quit, once confirmed".to_string()
+        } else {
+            "quit".to_string()
+        },
+    ));
+    bindings
+}
+
+/// Display the keys that can be pressed until the user presses any key.
+/// It will also forward requests to stop the program (and Errors).
+fn help(io: &mut Io, opts: &Options, controls: Controls) -> Result<(), RepsError> {
+    let bindings = bindings(opts, controls);
+    let width = bindings.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    let mut message = "Keys\n\n".to_string();
+    for (key, description) in bindings {
+        message.push_str(&format!(
+            "{:>width$}  {}\n",
+            key,
+            description,
+            width = width
+        ));
+    }
+    message.push_str("\nPress any key to resume");
+    io.output.event(&Event::Pause);
+    io.output.render(&layout(io, opts, &message));
+    loop {
+        let keys = consume_all_keystrokes(io.input, opts.keys.quit, opts.confirm_exit)?;
+        if is_quit(&keys, opts.keys.quit) {
+            confirm_quit(io, opts)?;
+        }
+        if !keys.is_empty() {
+            io.output.event(&Event::Resume);
+            return Ok(());
+        }
+        if io.output.resized() {
+            io.output.render(&layout(io, opts, &message));
+        }
+        io.clock.sleep(POLL_INTERVAL)
+    }
+}
+
+/// Summary of the workout planned by the options, if it has an end
+fn summary(opts: &Options) -> Option<String> {
+    if !opts.summary || opts.resume_at.is_some() || opts.until_stop() {
+        return None;
+    }
+    match opts.mode {
+        Mode::Reps => Some(Schedule::from_options(opts).summary()),
+        Mode::Emom => Some(Schedule::emom(opts).summary()),
+        Mode::Stopwatch => None,
+    }
+}
+
+/// Display the summary of the workout with `summary`, and wait until the user presses any key to
+/// start it: for `SUMMARY_DURATION` at most, unless `wait_for_key` is set too.
+/// It will also forward requests to stop the program (and Errors).
+pub fn wait_to_start(io: &mut Io, opts: &Options) -> Result<(), RepsError> {
+    let mut lines: Vec<String> = summary(opts).into_iter().collect();
+    if opts.wait_for_key {
+        lines.push("Press any key to start".to_string());
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let message = lines.join("\n");
+    let deadline = (!opts.wait_for_key).then(|| io.clock.now() + SUMMARY_DURATION);
+    io.output.render(&layout(io, opts, &message));
+    loop {
+        let keys = consume_all_keystrokes(io.input, opts.keys.quit, opts.confirm_exit)?;
+        if is_quit(&keys, opts.keys.quit) {
+            confirm_quit(io, opts)?;
+            io.output.render(&layout(io, opts, &message));
+        } else if !keys.is_empty() {
+            return Ok(());
+        } else if io.output.resized() {
+            io.output.render(&layout(io, opts, &message));
+        }
+        if deadline.is_some_and(|deadline| io.clock.now() >= deadline) {
+            return Ok(());
+        }
+        io.clock.sleep(POLL_INTERVAL)
+    }
+}
+
+/// Pause until the user press the pause key again, displaying for how long the workout has been
+/// paused, updated every second, and `back_to`: where it goes on from once resumed.
+/// It will also forward requests to stop the program (and Errors), and stops once the `cap` of
+/// the workout is reached, if given.
+fn pause(
+    io: &mut Io,
+    opts: &Options,
+    back_to: &str,
+    cap: Option<Instant>,
+) -> Result<(), RepsError> {
+    io.output.event(&Event::Pause);
+    let start = io.clock.now();
+    let render = |io: &mut Io, seconds: u64| {
+        let back_to = wrap(back_to, io.output.size().0);
+        let message = format!("PAUSED {}\n{}", format_duration(seconds), back_to);
+        io.output.render(&layout(io, opts, &message));
+    };
+    let mut shown = 0;
+    render(io, shown);
+    loop {
+        let keys = consume_all_keystrokes(io.input, opts.keys.quit, opts.confirm_exit)?;
+        if cap.is_some_and(|cap| io.clock.now() >= cap) {
+            return Err(RepsError::TimeCap);
+        }
+        let seconds = (io.clock.now() - start).as_secs();
+        if is_quit(&keys, opts.keys.quit) {
+            confirm_quit(io, opts)?;
+            shown = (io.clock.now() - start).as_secs();
+            render(io, shown);
+        } else if keys.contains(&opts.keys.pause) {
+            io.output.event(&Event::Resume);
+            return Ok(());
+        } else if io.output.resized() || seconds != shown {
+            shown = seconds;
+            render(io, shown);
+        }
+        match cap {
+            Some(cap) => io
+                .clock
+                .sleep_until((io.clock.now() + POLL_INTERVAL).min(cap)),
+            None => io.clock.sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+/// Why `wait` returned
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Wake {
+    Elapsed,
+    Skip,
+    Adjust(i64),
+    Restart,
+    Previous,
+    Ready,
+    /// The screen must be drawn again, after a pause or when the terminal was resized
+    Redraw,
+    /// The sounds were turned off or on again
+    Muted,
+}
+
+/// Tick on the beat during a rep, by ringing the bell and flashing the screen
+struct Metronome {
+    interval: Duration,
+    next_beat: Cell<Instant>,
+    /// Whether the screen is flashing and must be restored
+    flashing: Cell<bool>,
+}
+
+impl Metronome {
+    fn new(io: &Io, bpm: u32) -> Metronome {
+        let interval = Duration::from_secs(60) / bpm;
+        Metronome {
+            interval,
+            next_beat: Cell::new(io.clock.now() + interval),
+            flashing: Cell::new(false),
+        }
+    }
+
+    /// Tick if the next beat is due, or restore the screen after the previous one.
+    fn tick(&self, io: &mut Io, opts: &Options, muted: bool, message: &str) {
+        if self.flashing.replace(false) {
+            io.output.render(message);
+        }
+        let now = io.clock.now();
+        if now < self.next_beat.get() {
+            return;
+        }
+        while self.next_beat.get() <= now {
+            self.next_beat.set(self.next_beat.get() + self.interval);
+        }
+        if !muted {
+            io.output.bell();
+        }
+        if !opts.no_color && opts.cue_intensity != CueIntensity::None {
+            io.output
+                .render(&format!("{}{}{}", style::Invert, message, style::NoInvert));
+            self.flashing.set(true);
+        }
+    }
+
+    /// Start counting the beats again after a pause.
+    fn resume(&self, io: &Io) {
+        self.next_beat.set(io.clock.now() + self.interval);
+        self.flashing.set(false);
+    }
+}
+
+/// Animation showing that the countdown is running
+struct Spinner {
+    start: Instant,
+    /// Index of the frame displayed
+    frame: Cell<usize>,
+}
+
+impl Spinner {
+    fn new(io: &Io) -> Spinner {
+        Spinner {
+            start: io.clock.now(),
+            frame: Cell::new(0),
+        }
+    }
+
+    fn index(&self, io: &Io) -> usize {
+        let elapsed = io.clock.now() - self.start;
+        (elapsed.as_millis() / SPINNER_INTERVAL.as_millis()) as usize % SPINNER_FRAMES.len()
+    }
+
+    /// Frame to display now
+    fn frame(&self, io: &Io) -> char {
+        self.frame.set(self.index(io));
+        SPINNER_FRAMES[self.frame.get()]
+    }
+
+    /// Whether the frame displayed is not the current one anymore
+    fn advanced(&self, io: &Io) -> bool {
+        self.index(io) != self.frame.get()
+    }
+}
+
+/// Screen displayed while waiting
+struct Displayed<'a> {
+    /// Message currently displayed, restored after the flashes of the metronome
+    message: &'a str,
+    /// Description of the message on the pause screen
+    back_to: &'a str,
+}
+
+/// What goes on along with a countdown, each of them optional
+#[derive(Clone, Copy, Default)]
+struct Running<'a> {
+    session: Option<&'a Session>,
+    metronome: Option<&'a Metronome>,
+    spinner: Option<&'a Spinner>,
+}
+
+/// Wait until the `deadline` while checking for user input every POLL_INTERVAL, so that requests
+/// to stop the program are handled quickly. Waiting against a deadline rather than for a duration
+/// counts the time spent drawing the screen as well, so that the countdowns do not drift.
+/// The time spent in pause is not counted: the deadline is pushed back by as much.
+/// `message` is the one currently displayed, restored after the flashes of the metronome, and
+/// `back_to` describes it on the pause screen.
+/// It returns early when the user requests to skip or adjust the countdown, or when the screen
+/// must be drawn again, waiting can then be resumed with the same `deadline`. The `controls` are
+/// listed when the user asks for help, which is not counted in the countdown either. The pauses
+/// are recorded in the `session`, if any, the `metronome` ticks on the beat and the screen is
+/// drawn again when the `spinner` advances. It stops once the workout reaches its time cap.
+fn wait(
+    io: &mut Io,
+    opts: &Options,
+    deadline: &mut Instant,
+    controls: Controls,
+    Displayed { message, back_to }: Displayed,
+    Running {
+        session,
+        metronome,
+        spinner,
+    }: Running,
+) -> Result<Wake, RepsError> {
+    while io.clock.now() < *deadline {
+        let cap = session.and_then(|session| session.cap(opts));
+        if cap.is_some_and(|cap| io.clock.now() >= cap) {
+            return Err(RepsError::TimeCap);
+        }
+        if let Some(metronome) = metronome {
+            metronome.tick(io, opts, is_muted(session), message);
+        }
+        let left = deadline.saturating_duration_since(io.clock.now());
+        let wake_up = (io.clock.now() + POLL_INTERVAL).min(*deadline);
+        io.clock
+            .sleep_until(cap.map_or(wake_up, |cap| wake_up.min(cap)));
+        let keys = consume_all_keystrokes(io.input, opts.keys.quit, opts.confirm_exit)?;
+        // Neither the pause nor the confirmation to quit count in the countdown
+        let suspended = io.clock.now();
+        let suspend = |io: &Io, deadline: &mut Instant| {
+            let paused = io.clock.now() - suspended;
+            *deadline += paused;
+            if let Some(session) = session {
+                session.paused.set(session.paused.get() + paused);
+            }
+        };
+        match action(&keys, &opts.keys) {
+            Some(Action::Pause) => {
+                if let Some(session) = session {
+                    session.pauses.set(session.pauses.get() + 1);
+                }
+                // Reached during the pause, unless it does not count
+                let cap = cap.filter(|_| !opts.cap_excludes_pauses);
+                let result = pause(io, opts, back_to, cap);
+                suspend(io, deadline);
+                result?;
+                if let Some(metronome) = metronome {
+                    metronome.resume(io);
+                }
+                return Ok(Wake::Redraw);
+            }
+            Some(Action::Quit) => {
+                let result = confirm_quit(io, opts);
+                suspend(io, deadline);
+                result?;
+                return Ok(Wake::Redraw);
+            }
+            Some(Action::Help) => {
+                let result = help(io, opts, controls);
+                suspend(io, deadline);
+                result?;
+                if let Some(metronome) = metronome {
+                    metronome.resume(io);
+                }
+                return Ok(Wake::Redraw);
+            }
+            Some(Action::Skip) => return Ok(Wake::Skip),
+            Some(Action::Adjust(seconds)) => return Ok(Wake::Adjust(seconds)),
+            Some(Action::Restart) => return Ok(Wake::Restart),
+            Some(Action::Previous) => return Ok(Wake::Previous),
+            Some(Action::Ready) => return Ok(Wake::Ready),
+            Some(Action::Mute) => {
+                // Nothing to mute otherwise
+                if let Some(session) = session.filter(|_| opts.has_sound()) {
+                    session.muted.set(!session.muted.get());
+                    return Ok(Wake::Muted);
+                }
+            }
+            None if io.output.resized() => return Ok(Wake::Redraw),
+            // The tenths of a second are displayed in the countdowns
+            None if opts.precise
+                && controls != Controls::Stopwatch
+                && io.clock.now() < *deadline
+                && tenths_until(io, *deadline) != tenths(left) =>
+            {
+                return Ok(Wake::Redraw)
+            }
+            // No need to draw it again if the countdown goes on to the next second
+            None if io.clock.now() < *deadline
+                && spinner.is_some_and(|spinner| spinner.advanced(io)) =>
+            {
+                return Ok(Wake::Redraw)
+            }
+            None => {}
+        }
+    }
+    Ok(Wake::Elapsed)
+}
+
+/// Draw a bar of `width` characters, filled according to `progress` (between 0 and 1).
+fn progress_bar(progress: f64, width: u16) -> String {
+    if width < MIN_BLOCK_BAR_WIDTH {
+        let inner = width.saturating_sub(2) as usize;
+        let filled = (progress * inner as f64).round() as usize;
+        format!("[{}{}]", "#".repeat(filled), "-".repeat(inner - filled))
+    } else {
+        let filled = (progress * width as f64).round() as usize;
+        format!(
+            "{}{}",
+            "█".repeat(filled),
+            "░".repeat(width as usize - filled)
+        )
+    }
+}
+
+/// Render the remaining time with large digits, centered on a screen of the given size.
+/// The first line of the screen is left empty for the label.
+fn big_seconds(time: &str, (width, height): (u16, u16)) -> String {
+    let digits = big::render(time);
+    let top = (height as usize).saturating_sub(big::HEIGHT) / 2;
+    let left = (width as usize).saturating_sub(digits[0].chars().count()) / 2;
+    let margin = " ".repeat(left);
+    let lines: Vec<String> = digits
+        .iter()
+        .map(|line| format!("{}{}", margin, line))
+        .collect();
+    format!("{}{}", "\n".repeat(top.max(1) - 1), lines.join("\n"))
+}
+
+/// Number of characters displayed on the line, without the escape sequences
+fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            c if in_escape => in_escape = !c.is_ascii_alphabetic(),
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+/// Break the lines of the text between the words so that none is wider than `width` characters.
+/// The words that are wider than that on their own are cut.
+fn wrap(text: &str, width: u16) -> String {
+    let width = (width as usize).max(1);
+    let mut lines = vec![];
+    for line in text.lines() {
+        if line.chars().count() <= width {
+            lines.push(line.to_string());
+            continue;
+        }
+        let mut current = String::new();
+        for word in line.split(' ').filter(|word| !word.is_empty()) {
+            let mut word: Vec<char> = word.chars().collect();
+            if word.len() > width && !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            while word.len() > width {
+                lines.push(word.drain(..width).collect());
+            }
+            let length = current.chars().count();
+            if length > 0 && length + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.extend(word);
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+    }
+    lines.join("\n")
+}
+
+/// Center each line of the message on the screen, if enabled by the user.
+fn layout(io: &Io, opts: &Options, message: &str) -> String {
+    if !opts.center || opts.quiet {
+        return message.to_string();
+    }
+    let (width, height) = io.output.size();
+    let lines: Vec<&str> = message.lines().collect();
+    let top = (height as usize).saturating_sub(lines.len()) / 2;
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let left = (width as usize).saturating_sub(visible_width(line)) / 2;
+            format!("{}{}", " ".repeat(left), line)
+        })
+        .collect();
+    format!("{}{}", "\n".repeat(top), lines.join("\n"))
+}
+
+/// Color of a countdown, which can fade from green to red as the time runs out
+#[derive(Debug)]
+struct Shade<'a> {
+    /// Color used when it does not fade
+    base: &'a dyn Color,
+    /// Fraction of the countdown remaining, and whether 24-bit colors can be used
+    fade: Option<(f64, bool)>,
+}
+
+impl Shade<'_> {
+    /// Write the sequence of the current color, using `write` of the chosen color.
+    fn write(
+        &self,
+        f: &mut fmt::Formatter,
+        write: fn(&dyn Color, &mut fmt::Formatter) -> fmt::Result,
+    ) -> fmt::Result {
+        match self.fade {
+            None => write(self.base, f),
+            Some((remaining, true)) => {
+                // Green, then yellow at the middle, then red
+                let red = ((1.0 - remaining) * 2.0).min(1.0);
+                let green = (remaining * 2.0).min(1.0);
+                let rgb = color::Rgb((red * 255.0) as u8, (green * 255.0) as u8, 0);
+                write(&rgb, f)
+            }
+            // Nearest ANSI colors
+            Some((remaining, false)) if remaining > 2.0 / 3.0 => write(&color::Green, f),
+            Some((remaining, false)) if remaining > 1.0 / 3.0 => write(&color::Yellow, f),
+            Some(_) => write(&color::Red, f),
+        }
+    }
+}
+
+impl Color for Shade<'_> {
+    fn write_fg(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, |color, f| color.write_fg(f))
+    }
+
+    fn write_bg(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, |color, f| color.write_bg(f))
+    }
+}
+
+/// Color of a phase, from the palette or from the theme
+#[derive(Clone, Copy, Debug)]
+enum Tint {
+    Palette(&'static dyn Color),
+    Rgb(color::Rgb),
+    /// Nearest of the 256 colors, when the terminal does not support 24-bit colors
+    Ansi(color::AnsiValue),
+}
+
+impl Tint {
+    fn color(&self) -> &dyn Color {
+        match self {
+            Tint::Palette(color) => *color,
+            Tint::Rgb(color) => color,
+            Tint::Ansi(color) => color,
+        }
+    }
+}
+
+impl Color for Tint {
+    fn write_fg(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.color().write_fg(f)
+    }
+
+    fn write_bg(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.color().write_bg(f)
+    }
+}
+
+/// Escape sequence setting the foreground color, or nothing if colors are disabled.
+fn fg(opts: &Options, color: &dyn Color) -> String {
+    if opts.no_color {
+        String::new()
+    } else {
+        color::Fg(color).to_string()
+    }
+}
+
+/// Progress in the whole workout
+struct Session {
+    start: Instant,
+    /// Scheduled duration of the workout, in seconds, unknown when it goes on until stopped
+    total: Option<u64>,
+    /// Time spent in pause, or confirming to quit
+    paused: Cell<Duration>,
+    pauses: Cell<u32>,
+    /// Reps of the workout, when they are planned
+    reps: Option<RepCount>,
+    /// Text and duration of each phase of the workout, listed with `tui`
+    plan: Vec<(String, u32)>,
+    /// Position of the phase running in the plan, from 0
+    current: Cell<usize>,
+    /// The bells, sounds and speech are turned off for now, with 'm' during the workout
+    muted: Cell<bool>,
+}
+
+/// Reps done out of all the reps of the workout
+struct RepCount {
+    done: Cell<u32>,
+    total: u32,
+    /// Color of the reps, for the ones done
+    color: Tint,
+}
+
+impl Session {
+    fn new(io: &Io, total: Option<u64>) -> Session {
+        Session {
+            start: io.clock.now(),
+            total,
+            paused: Cell::new(Duration::from_secs(0)),
+            pauses: Cell::new(0),
+            reps: None,
+            plan: vec![],
+            current: Cell::new(0),
+            muted: Cell::new(false),
+        }
+    }
+
+    /// Dots for the reps of the workout, filled for the ones done, e.g. "●●●○○", or the number of
+    /// reps done when there are too many of them to fit in `width` characters.
+    fn rep_dots(&self, opts: &Options, width: u16) -> Option<String> {
+        let reps = self.reps.as_ref().filter(|reps| reps.total > 0)?;
+        let done = reps.done.get().min(reps.total);
+        if reps.total > MAX_REP_DOTS.min(width as u32) {
+            return Some(format!("Reps {}/{}", done, reps.total));
+        }
+        let (faint, reset) = if opts.no_color {
+            (String::new(), String::new())
+        } else {
+            (style::Faint.to_string(), style::Reset.to_string())
+        };
+        Some(format!(
+            "{}{}{}{}{}",
+            fg(opts, &reps.color),
+            "●".repeat(done as usize),
+            faint,
+            "○".repeat((reps.total - done) as usize),
+            reset
+        ))
+    }
+
+    /// Time spent in the workout, without the pauses
+    fn active(&self, io: &Io) -> Duration {
+        (io.clock.now() - self.start).saturating_sub(self.paused.get())
+    }
+
+    /// When the workout reaches its `max_duration`, if any, the pauses done so far being added
+    /// unless they count
+    fn cap(&self, opts: &Options) -> Option<Instant> {
+        let cap = self.start + Duration::from_secs(opts.max_duration? as u64);
+        Some(if opts.cap_excludes_pauses {
+            cap + self.paused.get()
+        } else {
+            cap
+        })
+    }
+
+    /// Line displaying the time since the start of the workout (including the pauses) and its
+    /// scheduled duration, when it is known
+    fn status(&self, io: &Io) -> String {
+        let elapsed = format_duration((io.clock.now() - self.start).as_secs());
+        match self.total {
+            Some(total) => format!("Elapsed {} / Total {}", elapsed, format_duration(total)),
+            None => format!("Elapsed {}", elapsed),
+        }
+    }
+
+    /// Thin bar filled according to the time spent in the countdowns, so that it does not move
+    /// during the pauses. There is nothing to fill when the duration is unknown.
+    fn progress_bar(&self, io: &Io, width: u16) -> Option<String> {
+        let progress = match self.total? {
+            0 => 1.0,
+            total => (self.active(io).as_secs_f64() / total as f64).min(1.0),
+        };
+        let filled = (progress * width as f64).round() as usize;
+        Some(format!(
+            "{}{}",
+            "━".repeat(filled),
+            "─".repeat(width as usize - filled)
+        ))
+    }
+}
+
+/// Number of tenths of a second started in the duration
+fn tenths(duration: Duration) -> u32 {
+    duration.as_millis().div_ceil(100) as u32
+}
+
+/// Number of tenths of a second started until the deadline
+fn tenths_until(io: &Io, deadline: Instant) -> u32 {
+    tenths(deadline.saturating_duration_since(io.clock.now()))
+}
+
+/// Remaining time of a countdown of `total` seconds, e.g. "5s", or "4.3s" with the tenths of a
+/// second. The countdowns over a minute, or all of them with `always_mmss`, are displayed as
+/// "1:30": the time then keeps the same width until the end, e.g. "0:05:00" in a countdown of an
+/// hour.
+fn remaining_text(opts: &Options, sec: u32, tenths: Option<u32>, total: u32) -> String {
+    let sec = tenths.map_or(sec, |tenths| tenths / 10);
+    let text = if total >= 3600 {
+        format!("{}:{:02}:{:02}", sec / 3600, sec / 60 % 60, sec % 60)
+    } else if total >= 600 {
+        format!("{:02}:{:02}", sec / 60, sec % 60)
+    } else if total > 60 || opts.always_mmss {
+        format!("{}:{:02}", sec / 60, sec % 60)
+    } else {
+        sec.to_string()
+    };
+    let unit = if text.contains(':') { "" } else { "s" };
+    match tenths {
+        Some(tenths) => format!("{}.{}{}", text, tenths % 10, unit),
+        None => format!("{}{}", text, unit),
+    }
+}
+
+/// Phrase of the motivation displayed `elapsed` seconds into a rep. Each rep starts with the
+/// phrase following the one the previous rep started with. Nothing is displayed with large
+/// digits, which already fill the screen.
+fn motivation<'a>(
+    opts: &'a Options,
+    elapsed: u32,
+    controls: Controls,
+    session: Option<&Session>,
+) -> Option<&'a str> {
+    if opts.motivation.is_empty() || opts.big || controls != Controls::Rep {
+        return None;
+    }
+    let done = session
+        .and_then(|session| session.reps.as_ref())
+        .map_or(0, |reps| reps.done.get());
+    let index = (done + elapsed / MOTIVATION_SECONDS) as usize % opts.motivation.len();
+    Some(&opts.motivation[index])
+}
+
+/// Time left in a countdown
+struct Remaining {
+    sec: u32,
+    /// Tenths of a second left instead, when the time is displayed precisely
+    tenths: Option<u32>,
+    /// Duration of the countdown, including the time added by the user
+    total: u32,
+}
+
+/// What is displayed along with the remaining time
+struct Extras<'a> {
+    /// Progress of the workout, if any
+    session: Option<&'a Session>,
+    note: Option<&'a str>,
+    /// Frame of the spinner
+    spinner: Option<char>,
+}
+
+/// Build the message displayed while `sec` seconds remain out of `total`, with the progress of
+/// the workout and an optional note displayed below it. The remaining time is displayed in
+/// `tenths` of a second instead, if given. The frame of the spinner, if any, is displayed next
+/// to the remaining time.
+fn countdown_message(
+    io: &Io,
+    opts: &Options,
+    label: &str,
+    Remaining { sec, tenths, total }: Remaining,
+    color: &dyn Color,
+    controls: Controls,
+    Extras {
+        session,
+        note,
+        spinner,
+    }: Extras,
+) -> String {
+    let size = io.output.size();
+    let progress = (total - sec) as f64 / total as f64;
+    let seconds = if opts.big {
+        // Without the unit nor the tenths of a second
+        let text = remaining_text(opts, sec, None, total);
+        let text = text.trim_end_matches('s');
+        if opts.center {
+            // Centered with the rest of the message
+            big::render(text).join("\n")
+        } else {
+            big_seconds(text, size)
+        }
+    } else {
+        remaining_text(opts, sec, tenths, total)
+    };
+    let bold = if opts.bold && !opts.no_color {
+        style::Bold.to_string()
+    } else {
+        String::new()
+    };
+    // The last seconds stand out, whatever the color of the countdown
+    let final_seconds = sec <= FINAL_SECONDS && opts.cue_intensity != CueIntensity::None;
+    let (seconds_style, seconds_reset) = match (opts.no_color, final_seconds) {
+        (false, true) => (
+            format!("{}{}", style::Bold, color::Fg(color::LightRed)),
+            style::Reset.to_string(),
+        ),
+        // Not to make the rest of the screen bold
+        _ if !bold.is_empty() => (
+            format!("{}{}", bold, fg(opts, &color::Blue)),
+            style::Reset.to_string(),
+        ),
+        _ => (fg(opts, &color::Blue), String::new()),
+    };
+    let spinner = spinner
+        .map(|frame| format!(" {}{}", fg(opts, color), frame))
+        .unwrap_or_default();
+    if opts.quiet {
+        return format!(
+            "{}{}{} {}{}{}{}",
+            bold,
+            fg(opts, color),
+            label.replace('\n', " "),
+            seconds_style,
+            remaining_text(opts, sec, tenths, total),
+            seconds_reset,
+            spinner
+        );
+    }
+    // Large digits take several lines, the spinner is then next to the label
+    let (label_spinner, seconds_spinner) = if opts.big {
+        (spinner.as_str(), "")
+    } else {
+        ("", spinner.as_str())
+    };
+    if let Some(session) = session.filter(|session| opts.tui && !session.plan.is_empty()) {
+        let notes: Vec<String> = motivation(opts, total - sec, controls, Some(session))
+            .map(|phrase| {
+                format!(
+                    "{}{}{}",
+                    fg(opts, &color::Yellow),
+                    phrase,
+                    fg(opts, &color::Reset)
+                )
+            })
+            .into_iter()
+            .chain(note.map(str::to_string))
+            .collect();
+        let seconds = format!("{}{}{}{}", seconds_style, seconds, seconds_reset, spinner);
+        return tui::message(
+            io,
+            opts,
+            (label, &seconds),
+            (progress, color),
+            controls,
+            session,
+            &notes,
+        );
+    }
+    let motivation = motivation(opts, total - sec, controls, session)
+        .map(|phrase| format!("\n{}{}", fg(opts, &color::Yellow), wrap(phrase, size.0)))
+        .unwrap_or_default();
+    // Long names of exercises would not fit on a narrow screen
+    let label = wrap(label, size.0);
+    let mut message = format!(
+        "{}{}{}{}\n{}{}{}{}{}\n{}{}",
+        bold,
+        fg(opts, color),
+        label,
+        label_spinner,
+        seconds_style,
+        seconds,
+        seconds_reset,
+        seconds_spinner,
+        motivation,
+        fg(opts, color),
+        progress_bar(progress, size.0)
+    );
+    if let Some(session) = session {
+        message = format!("{}\n{}", message, session.status(io));
+        if let Some(dots) = session.rep_dots(opts, size.0) {
+            message = format!("{}\n{}", message, dots);
+        }
+    }
+    if let Some(note) = note {
+        message = format!("{}\n{}", message, note);
+    }
+    let message = layout(io, opts, &message);
+    let mut bottom = vec![];
+    if let Some(bar) = session.and_then(|session| session.progress_bar(io, size.0)) {
+        bottom.push(format!("{}{}", fg(opts, &color::Blue), bar));
+    }
+    if opts.help_line {
+        bottom.push(help_line(opts, controls, size.0));
+    }
+    pin_to_bottom(io, &message, &bottom)
+}
+
+/// Display the `bottom` lines on the last lines of the screen, below the message.
+fn pin_to_bottom(io: &Io, message: &str, bottom: &[String]) -> String {
+    if bottom.is_empty() {
+        return message.to_string();
+    }
+    let lines = message.lines().count() + bottom.len() - 1;
+    format!(
+        "{}{}{}",
+        message,
+        "\n".repeat((io.output.size().1 as usize).saturating_sub(lines).max(1)),
+        bottom.join("\n")
+    )
+}
+
+/// Dim line listing the keys that can be pressed, cut to fit in `width` characters
+fn help_line(opts: &Options, controls: Controls, width: u16) -> String {
+    let line: String = bindings(opts, controls)
+        .iter()
+        .map(|(key, description)| format!("{} {}", key, description))
+        .collect::<Vec<_>>()
+        .join(" · ")
+        .chars()
+        .take(width as usize)
+        .collect();
+    if opts.no_color {
+        line
+    } else {
+        format!("{}{}{}", style::Faint, line, style::Reset)
+    }
+}
+
+/// Label of a countdown as it should be spoken, e.g. "Set 1 of 2, Rep 3 of 10"
+fn spoken_label(label: &str) -> String {
+    // The symbols of the colorblind palette are only meant to be seen
+    label
+        .trim_start_matches(['▲', '●', ' '])
+        .replace('/', " of ")
+        .replace('\n', ", ")
+}
+
+/// Display a countdown with the specific label and colors.
+/// It will periodically check if the user entered any input and forward requests to stop the
+/// program as errors. It returns early if the user requested to skip it. The user can also add
+/// or remove time, and the countdown ends as soon as there is no time left.
+pub fn countdown(
+    io: &mut Io,
+    opts: &Options,
+    label: &str,
+    count: u32,
+    color: &dyn Color,
+) -> Result<Outcome, RepsError> {
+    let countdown = Countdown {
+        label,
+        count,
+        color,
+    };
+    run_countdown(io, opts, countdown, None, Controls::Countdown)
+}
+
+/// Fill the screen with the color of the countdown starting for a moment, a cue that can be seen
+/// from across the room. Without colors, the screen is filled with the color of the text. It is
+/// part of the first second of the countdown, the keys pressed meanwhile are handled right after.
+fn flash(io: &mut Io, opts: &Options, color: &dyn Color) {
+    let (width, height) = io.output.size();
+    let fill = vec![" ".repeat(width as usize); height as usize].join("\n");
+    let fill = if opts.no_color {
+        format!("{}{}{}", style::Invert, fill, style::NoInvert)
+    } else {
+        format!("{}{}{}", color::Bg(color), fill, color::Bg(color::Reset))
+    };
+    io.output.render(&fill);
+    io.clock.sleep(FLASH_DURATION);
+}
+
+/// Countdown of `count` seconds, displayed with its `label` and `color`
+#[derive(Clone, Copy)]
+struct Countdown<'a> {
+    label: &'a str,
+    count: u32,
+    color: &'a dyn Color,
+}
+
+/// Same as `countdown`, but it also displays the progress of the workout, if any. The `controls`
+/// tell which kind of countdown it is. A rep returns early if the user requested to start it
+/// again, and the cadence is ticked. A rest returns early once the user is ready.
+fn run_countdown(
+    io: &mut Io,
+    opts: &Options,
+    Countdown {
+        label,
+        count,
+        color,
+    }: Countdown,
+    session: Option<&Session>,
+    controls: Controls,
+) -> Result<Outcome, RepsError> {
+    let rep = controls == Controls::Rep;
+    let metronome = if rep && opts.cadence > 0 {
+        Some(Metronome::new(io, opts.cadence))
+    } else {
+        None
+    };
+    let spinner = if opts.spinner {
+        Some(Spinner::new(io))
+    } else {
+        None
+    };
+    let mut remaining = count;
+    // End of the current second, from the start of the countdown so that it does not drift
+    let mut deadline = io.clock.now() + DURATION_1_SECOND;
+    if opts.cue_intensity == CueIntensity::Strong && !opts.quiet && count > 0 {
+        flash(io, opts, color);
+    }
+    // Total duration of the countdown, including the time added by the user
+    let mut total = count;
+    if opts.speak && !is_muted(session) && count > 0 {
+        io.output.speak(&spoken_label(label));
+    }
+    // The color of the reps fades as the time runs out, if enabled
+    let shade = |remaining: u32, total: u32| Shade {
+        base: color,
+        fade: (rep && opts.fade).then(|| (remaining as f64 / total as f64, opts.truecolor)),
+    };
+    // Only signaled once, even if the user adds time afterwards
+    let mut halfway = rep && opts.halfway;
+    // Time left in the countdown when it is displayed precisely, the current second ending at
+    // the deadline
+    let tenths_left = |io: &Io, deadline: Instant, remaining: u32| {
+        (opts.precise && !opts.big).then(|| (remaining - 1) * 10 + tenths_until(io, deadline))
+    };
+    while remaining > 0 {
+        // Displayed below the countdown until the next second
+        let mut note = if halfway && remaining == count / 2 {
+            halfway = false;
+            if !is_muted(session) {
+                io.output.bell();
+            }
+            Some("Halfway!".to_string())
+        } else {
+            None
+        };
+        let mut message = countdown_message(
+            io,
+            opts,
+            label,
+            Remaining {
+                sec: remaining,
+                tenths: tenths_left(io, deadline, remaining),
+                total,
+            },
+            &shade(remaining, total),
+            controls,
+            Extras {
+                session,
+                note: note.as_deref(),
+                spinner: spinner.as_ref().map(|spinner| spinner.frame(io)),
+            },
+        );
+        io.output.render(&message);
+        io.output.event(&Event::Tick {
+            label,
+            elapsed: total - remaining,
+            remaining: Some(remaining),
+        });
+        if opts.set_title {
+            io.output.set_title(&format!(
+                "{} – {}",
+                label.replace('\n', " "),
+                format_duration(remaining as u64)
+            ));
+        }
+        // The first second is left for the label
+        if opts.speak && !is_muted(session) && remaining <= FINAL_SECONDS && remaining < total {
+            io.output.speak(&remaining.to_string());
+        }
+        // A single bell each second, after the one ending the rep
+        if opts.get_ready
+            && controls == Controls::Rest
+            && !is_muted(session)
+            && remaining <= FINAL_SECONDS
+            && remaining < total
+        {
+            io.output.bell();
+        }
+
+        loop {
+            let back_to = format!(
+                "Back to {}, {} left",
+                label.replace('\n', ", "),
+                remaining_text(opts, remaining, None, total)
+            );
+            match wait(
+                io,
+                opts,
+                &mut deadline,
+                controls,
+                Displayed {
+                    message: &message,
+                    back_to: &back_to,
+                },
+                Running {
+                    session,
+                    metronome: metronome.as_ref(),
+                    spinner: spinner.as_ref(),
+                },
+            )? {
+                Wake::Elapsed => {
+                    // The next second is shorter than this one was late, as its deadline comes
+                    // from the start of the countdown
+                    let late = io.clock.now().saturating_duration_since(deadline);
+                    if late >= LATE_SECOND {
+                        io.output.log(&format!(
+                            "Second ended {}ms late, the next one is shorter",
+                            late.as_millis()
+                        ));
+                    }
+                    remaining -= 1;
+                    deadline += DURATION_1_SECOND;
+                    break;
+                }
+                Wake::Skip => return Ok(Outcome::Skipped),
+                Wake::Previous => return Ok(Outcome::Previous),
+                Wake::Restart if rep => return Ok(Outcome::Restarted),
+                Wake::Restart => {}
+                Wake::Ready if controls == Controls::Rest => return Ok(Outcome::Skipped),
+                Wake::Ready => {}
+                Wake::Adjust(seconds) => {
+                    remaining = (remaining as i64 + seconds).clamp(0, u32::MAX as i64) as u32;
+                    total = total.max(remaining);
+                    if remaining == 0 {
+                        break;
+                    }
+                    note = Some(format!("{:+}s", seconds));
+                    message = countdown_message(
+                        io,
+                        opts,
+                        label,
+                        Remaining {
+                            sec: remaining,
+                            tenths: tenths_left(io, deadline, remaining),
+                            total,
+                        },
+                        &shade(remaining, total),
+                        controls,
+                        Extras {
+                            session,
+                            note: note.as_deref(),
+                            spinner: spinner.as_ref().map(|spinner| spinner.frame(io)),
+                        },
+                    );
+                    io.output.render(&message);
+                }
+                wake @ (Wake::Redraw | Wake::Muted) => {
+                    if wake == Wake::Muted {
+                        note = Some(
+                            if is_muted(session) {
+                                "Muted"
+                            } else {
+                                "Sound on"
+                            }
+                            .to_string(),
+                        );
+                    }
+                    message = countdown_message(
+                        io,
+                        opts,
+                        label,
+                        Remaining {
+                            sec: remaining,
+                            tenths: tenths_left(io, deadline, remaining),
+                            total,
+                        },
+                        &shade(remaining, total),
+                        controls,
+                        Extras {
+                            session,
+                            note: note.as_deref(),
+                            spinner: spinner.as_ref().map(|spinner| spinner.frame(io)),
+                        },
+                    );
+                    io.output.render(&message);
+                }
+            }
+        }
+    }
+    Ok(Outcome::Completed)
+}
+
+/// Whether the sounds of the workout are turned off for now, if there is a workout.
+fn is_muted(session: Option<&Session>) -> bool {
+    session.is_some_and(|session| session.muted.get())
+}
+
+/// Ring the bell `times` times, if enabled by the user.
+fn ring_bell(io: &mut Io, opts: &Options, session: &Session, times: u32) {
+    if !opts.bell || session.muted.get() {
+        return;
+    }
+    for i in 0..times {
+        if i > 0 {
+            io.clock.sleep(BELL_INTERVAL);
+        }
+        io.output.bell();
+    }
+}
+
+/// Signal the end of the whole workout, with the bells and the sound enabled by the user.
+fn ring_fanfare(io: &mut Io, opts: &Options, session: &Session) {
+    if opts.bell && !session.muted.get() {
+        // Apart from the bell ending the last rep, which the workout usually ends with
+        io.clock.sleep(FANFARE[FANFARE.len() - 1]);
+        io.output.bell();
+        for silence in FANFARE {
+            io.clock.sleep(silence);
+            io.output.bell();
+        }
+    }
+    play_sound(io, session, &opts.sound_done);
+}
+
+/// Play the sound file, if enabled by the user.
+fn play_sound(io: &mut Io, session: &Session, sound: &Option<PathBuf>) {
+    if let Some(path) = sound {
+        if !session.muted.get() {
+            io.output.play_sound(path);
+        }
+    }
+}
+
+/// Describe the workout without running it: each countdown on its own line with its color and
+/// its duration, then the total duration.
+pub fn dry_run(opts: &Options) -> String {
+    if opts.mode == Mode::Stopwatch {
+        return "Stopwatch, until stopped\n".to_string();
+    }
+    let mut schedule = Schedule::from_options(opts);
+    if opts.until_stop() {
+        // A single cycle, for the colors and the durations
+        schedule.phases.push(schedule::endless_rep(opts, 1));
+        if opts.relax_time > 0 || opts.relax_range.is_some() {
+            schedule.phases.push(Phase::Relax(opts.relax_time));
+        }
+    }
+    let labels: Vec<String> = schedule
+        .phases
+        .iter()
+        .map(|phase| phase.label(opts).replace('\n', " "))
+        .collect();
+    let width = labels
+        .iter()
+        .map(|label| visible_width(label))
+        .max()
+        .unwrap_or(0);
+    let colors: Vec<String> = schedule
+        .phases
+        .iter()
+        .map(|phase| match phase.theme_color(&opts.theme) {
+            Some((red, green, blue)) => format!("#{:02x}{:02x}{:02x}", red, green, blue),
+            None => phase.named_color(opts.palette).0.to_string(),
+        })
+        .collect();
+    // As wide as "#rrggbb" at least, the names of the bright colors are longer
+    let color_width = colors.iter().map(String::len).max().unwrap_or(0).max(7);
+    let mut plan = String::new();
+    for ((phase, label), color) in schedule.phases.iter().zip(&labels).zip(&colors) {
+        // Picked again when the workout is run
+        let random = match phase {
+            Phase::Relax(_) if opts.relax_range.is_some() => " (random)",
+            _ => "",
+        };
+        plan.push_str(&format!(
+            "{}{}  {:<color_width$} {}{}\n",
+            label,
+            " ".repeat(width - visible_width(label)),
+            color,
+            format_duration(phase.duration() as u64),
+            random,
+            color_width = color_width
+        ));
+    }
+    if opts.shuffle && opts.seed.is_none() && opts.exercises.len() > 1 {
+        plan.push_str("The exercises are shuffled again when the workout is run\n");
+    }
+    if opts.until_stop() {
+        plan.push_str("Then again, until stopped\n");
+    } else {
+        plan.push_str(&format!(
+            "Total: {}\n",
+            format_duration(schedule.total_duration())
+        ));
+    }
+    plan
+}
+
+/// Display the countdowns using the values provided by the user.
+/// `stats` is updated with the summary of the workout, even if the user stops early.
+pub fn start_reps(io: &mut Io, opts: &Options, stats: &mut Stats) -> Result<(), RepsError> {
+    if !opts.until_stop() {
+        return run_schedule(io, opts, &Schedule::from_options(opts), stats);
+    }
+    let session = Session::new(io, None);
+    let result = run_until_stop(io, opts, &session, stats);
+    stats.duration = io.clock.now() - session.start;
+    stats.pauses = session.pauses.get();
+    result
+}
+
+/// Display the countdowns of the schedule, which may not follow the options, e.g. when it was
+/// loaded from a file. `stats` is updated as with `start_reps`.
+pub fn run_schedule(
+    io: &mut Io,
+    opts: &Options,
+    schedule: &Schedule,
+    stats: &mut Stats,
+) -> Result<(), RepsError> {
+    let phases = match opts.resume_at {
+        Some(position) => schedule.resumed(opts, position),
+        None => schedule.numbered(),
+    };
+    let total = phases
+        .iter()
+        .map(|(_, phase)| phase.duration() as u64)
+        .sum();
+    let mut session = Session::new(io, Some(total));
+    session.reps = phases
+        .iter()
+        .find(|(_, phase)| matches!(phase, Phase::Rep { .. }))
+        .map(|(_, rep)| RepCount {
+            done: Cell::new(0),
+            total: phases
+                .iter()
+                .filter(|(_, phase)| matches!(phase, Phase::Rep { .. }))
+                .count() as u32,
+            color: rep.color(opts),
+        });
+    if opts.tui {
+        session.plan = plan(opts, phases.iter().map(|(_, phase)| phase));
+    }
+    let result = run_reps(io, opts, &phases, &session, stats);
+    if result.is_ok() {
+        ring_fanfare(io, opts, &session);
+    }
+    stats.duration = io.clock.now() - session.start;
+    stats.pauses = session.pauses.get();
+    result
+}
+
+/// Text and duration of the phases listed with `tui`
+fn plan<'a>(opts: &Options, phases: impl Iterator<Item = &'a Phase>) -> Vec<(String, u32)> {
+    phases
+        .map(|phase| (phase.text(opts).replace('\n', ", "), phase.duration()))
+        .collect()
+}
+
+/// Run a countdown of the workout, adding the time spent in it to `spent`.
+fn timed_countdown(
+    io: &mut Io,
+    opts: &Options,
+    countdown: Countdown,
+    session: &Session,
+    controls: Controls,
+    spent: &mut Duration,
+) -> Result<Outcome, RepsError> {
+    let start = session.active(io);
+    let outcome = run_countdown(io, opts, countdown, Some(session), controls);
+    *spent += session.active(io) - start;
+    outcome
+}
+
+impl Phase {
+    /// Kind of phase, reported in the events
+    fn kind(&self) -> &'static str {
+        match self {
+            Phase::Prep(_) => "prep",
+            Phase::Warmup(_) => "warmup",
+            Phase::Rep { .. } => "rep",
+            Phase::Relax(_) => "relax",
+            Phase::SetRest(_) => "set_rest",
+            Phase::Cooldown(_) => "cooldown",
+        }
+    }
+
+    /// Log the end of the countdown of the phase, the `index`-th of the workout
+    fn end(&self, io: &mut Io, index: u32, outcome: Outcome) {
+        io.output
+            .log(&format!("End of {} {}: {:?}", self.kind(), index, outcome));
+    }
+
+    /// Report the start of the countdown of the phase, the `index`-th of the workout
+    fn start(&self, io: &mut Io, label: &str, index: u32) {
+        io.output.event(&Event::Start {
+            phase: self.kind(),
+            index,
+            label,
+            duration: self.duration(),
+        });
+    }
+
+    fn color(&self, opts: &Options) -> Tint {
+        // Each component from 0 to 5, in the cube of the 256 colors
+        let nearest = |component: u8| ((component as u16 * 5 + 127) / 255) as u8;
+        match self.theme_color(&opts.theme) {
+            Some((red, green, blue)) if opts.truecolor => Tint::Rgb(color::Rgb(red, green, blue)),
+            Some((red, green, blue)) => Tint::Ansi(color::AnsiValue::rgb(
+                nearest(red),
+                nearest(green),
+                nearest(blue),
+            )),
+            None => Tint::Palette(self.named_color(opts.palette).1),
+        }
+    }
+
+    /// Color of the phase in the theme, if any
+    fn theme_color(&self, theme: &Theme) -> Option<Rgb> {
+        match self {
+            Phase::Prep(_) => theme.prep,
+            Phase::Warmup(_) => theme.warmup,
+            Phase::Rep { .. } => theme.rep,
+            Phase::Relax(_) => theme.relax,
+            Phase::SetRest(_) => theme.set_rest,
+            Phase::Cooldown(_) => theme.cooldown,
+        }
+    }
+
+    /// Color of the countdown, and its name
+    fn named_color(&self, palette: Palette) -> (&'static str, &'static dyn Color) {
+        match (palette, self) {
+            (Palette::Standard, Phase::Prep(_)) => ("blue", &color::Blue),
+            (Palette::Standard, Phase::Rep { .. }) => ("red", &color::Red),
+            (Palette::Standard, Phase::Relax(_) | Phase::Cooldown(_)) => ("green", &color::Green),
+            (Palette::Colorblind, Phase::Prep(_)) => ("magenta", &color::Magenta),
+            (Palette::Colorblind, Phase::Rep { .. }) => ("orange", &ORANGE),
+            (Palette::Colorblind, Phase::Relax(_) | Phase::Cooldown(_)) => ("blue", &color::Blue),
+            (Palette::Bright, Phase::Prep(_)) => ("light blue", &color::LightBlue),
+            (Palette::Bright, Phase::Rep { .. }) => ("light red", &color::LightRed),
+            (Palette::Bright, Phase::Relax(_) | Phase::Cooldown(_)) => {
+                ("light green", &color::LightGreen)
+            }
+            (Palette::Bright, Phase::Warmup(_)) => ("light yellow", &color::LightYellow),
+            (Palette::Bright, Phase::SetRest(_)) => ("light cyan", &color::LightCyan),
+            (_, Phase::Warmup(_)) => ("yellow", &color::Yellow),
+            (_, Phase::SetRest(_)) => ("cyan", &color::Cyan),
+        }
+    }
+
+    /// Symbol displayed before the label, so that the color is not the only signal
+    fn symbol(&self, palette: Palette) -> Option<&'static str> {
+        match (palette, self) {
+            (Palette::Standard | Palette::Bright, _) => None,
+            (Palette::Colorblind, Phase::Rep { .. }) => Some("▲"),
+            (Palette::Colorblind, Phase::Relax(_) | Phase::SetRest(_)) => Some("●"),
+            (Palette::Colorblind, _) => None,
+        }
+    }
+
+    fn label(&self, opts: &Options) -> String {
+        let label = self.text(opts);
+        match self.symbol(opts.palette) {
+            Some(symbol) => format!("{} {}", symbol, label),
+            None => label,
+        }
+    }
+
+    fn text(&self, opts: &Options) -> String {
+        match self {
+            Phase::Prep(_) => "Starting in".to_string(),
+            Phase::Warmup(_) => "Warmup".to_string(),
+            Phase::Relax(_) => opts
+                .relax_message
+                .clone()
+                .unwrap_or_else(|| "Relax!".to_string()),
+            Phase::SetRest(_) => format!("{} rest", opts.set_label.as_deref().unwrap_or("Set")),
+            Phase::Cooldown(_) => "Cooldown".to_string(),
+            Phase::Rep {
+                exercise,
+                index,
+                total,
+                set: (set, sets),
+                duration,
+            } if opts.circuit => format!("{} – Round {}/{}", exercise, set, sets),
+            Phase::Rep {
+                exercise,
+                index,
+                total,
+                set: (set, sets),
+                duration,
+            } => {
+                let total_text = match total {
+                    0 => "∞".to_string(),
+                    total => total.to_string(),
+                };
+                let mut label = match (opts.names.get(*index as usize - 1), &opts.rep_message) {
+                    (Some(name), _) => name.clone(),
+                    (None, Some(message)) => message
+                        .replace("{n}", &index.to_string())
+                        .replace("{total}", &total_text),
+                    (None, None) if *total == 0 => format!("{} {}", exercise, index),
+                    (None, None) => format!("{} {}/{}", exercise, index, total),
+                };
+                if !opts.rep_times.is_empty() {
+                    label = format!("{} ({}s)", label, duration);
+                }
+                if *sets > 1 {
+                    let set_label = opts.set_label.as_deref().unwrap_or("Set");
+                    format!("{} {}/{}\n{}", set_label, set, sets, label)
+                } else {
+                    label
+                }
+            }
+        }
+    }
+}
+
+/// Phase of the workout about to run
+struct Step<'a> {
+    /// Position of the phase in the workout, from 1
+    index: u32,
+    phase: &'a Phase,
+    /// Phase coming after it, if any, announced with `show_next`
+    next: Option<&'a Phase>,
+}
+
+/// Display the countdown of the phase, with the signals for the start and the end of a rep.
+fn run_phase(
+    io: &mut Io,
+    opts: &Options,
+    Step { index, phase, next }: Step,
+    session: &Session,
+    stats: &mut Stats,
+) -> Result<Outcome, RepsError> {
+    let mut label = phase.label(opts);
+    if opts.show_next && matches!(phase, Phase::Relax(_) | Phase::SetRest(_)) {
+        let next = next.map_or("Done".to_string(), |next| next.text(opts));
+        label = format!("{}\nNext: {}", label, next.replace('\n', ", "));
+    }
+    let color = phase.color(opts);
+    let countdown = Countdown {
+        label: &label,
+        count: phase.duration(),
+        color: &color,
+    };
+    let outcome = match phase {
+        Phase::Rep { .. } => loop {
+            phase.start(io, &label, index);
+            ring_bell(io, opts, session, 2);
+            play_sound(io, session, &opts.sound_start);
+            let outcome = timed_countdown(
+                io,
+                opts,
+                countdown,
+                session,
+                Controls::Rep,
+                &mut stats.work_time,
+            )?;
+            match outcome {
+                Outcome::Restarted => continue,
+                Outcome::Previous => break outcome,
+                Outcome::Completed => stats.reps += 1,
+                Outcome::Skipped => {}
+            }
+            if opts.notify_each {
+                io.output.notify(format!("{} done, relax!", label).as_str());
+            }
+            ring_bell(io, opts, session, 1);
+            play_sound(io, session, &opts.sound_end);
+            break outcome;
+        },
+        Phase::Relax(_) | Phase::SetRest(_) => {
+            phase.start(io, &label, index);
+            timed_countdown(
+                io,
+                opts,
+                countdown,
+                session,
+                Controls::Rest,
+                &mut stats.rest_time,
+            )?
+        }
+        Phase::Prep(_) | Phase::Warmup(_) | Phase::Cooldown(_) => {
+            phase.start(io, &label, index);
+            run_countdown(io, opts, countdown, Some(session), Controls::Countdown)?
+        }
+    };
+    phase.end(io, index, outcome);
+    Ok(outcome)
+}
+
+/// Walk the schedule, one phase after the other, or back to the previous one if the user
+/// requested it. Going back before the first phase starts it again.
+fn run_reps(
+    io: &mut Io,
+    opts: &Options,
+    phases: &[(u32, Phase)],
+    session: &Session,
+    stats: &mut Stats,
+) -> Result<(), RepsError> {
+    // Whether the rep of each phase was completed, so that it is not counted twice once done again
+    let mut completed = vec![false; phases.len()];
+    let mut i = 0;
+    while i < phases.len() {
+        let (index, phase) = &phases[i];
+        // The rests are skipped, the user prepares for the exercise that follows them
+        let next = phases[i + 1..]
+            .iter()
+            .map(|(_, next)| next)
+            .find(|next| !matches!(next, Phase::Relax(_) | Phase::SetRest(_)));
+        session.current.set(i);
+        let outcome = run_phase(
+            io,
+            opts,
+            Step {
+                index: *index,
+                phase,
+                next,
+            },
+            session,
+            stats,
+        )?;
+        if outcome != Outcome::Previous {
+            completed[i] = outcome == Outcome::Completed;
+            if let (Phase::Rep { .. }, Some(reps)) = (phase, &session.reps) {
+                reps.done.set(reps.done.get() + 1);
+            }
+            i += 1;
+        } else if i > 0 {
+            i -= 1;
+            if let Phase::Rep { .. } = phases[i].1 {
+                if std::mem::take(&mut completed[i]) {
+                    stats.reps -= 1;
+                }
+                if let Some(reps) = &session.reps {
+                    reps.done.set(reps.done.get() - 1);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Walk what is planned before the reps, then go on with a rep and a relax, again and again,
+/// until the user stops.
+fn run_until_stop(
+    io: &mut Io,
+    opts: &Options,
+    session: &Session,
+    stats: &mut Stats,
+) -> Result<(), RepsError> {
+    let schedule = Schedule::from_options(opts);
+    run_reps(io, opts, &schedule.numbered(), session, stats)?;
+    let mut rng = schedule::rng(opts);
+    let start = schedule.phases.len() as u32;
+    // Each rep is followed by a relax, unless it lasts 0s. Going back from a rep leads to the
+    // relax before it. The first rep is started again instead, as what came before it is over.
+    let relax_after = |rng: &mut _| match schedule::relax(opts, opts.relax_time, rng) {
+        0 => None,
+        duration => Some(Phase::Relax(duration)),
+    };
+    let phases_per_rep = if opts.relax_time == 0 && opts.relax_range.is_none() {
+        1
+    } else {
+        2
+    };
+    let mut index = 1;
+    let mut relax = None;
+    loop {
+        let position = start + phases_per_rep * (index - 1) + 1;
+        let rep = schedule::endless_rep(opts, index);
+        let outcome = match &relax {
+            None => {
+                let step = Step {
+                    index: position,
+                    phase: &rep,
+                    next: None,
+                };
+                run_phase(io, opts, step, session, stats)?
+            }
+            Some(relax) => {
+                let next = schedule::endless_rep(opts, index + 1);
+                let step = Step {
+                    index: position + 1,
+                    phase: relax,
+                    next: Some(&next),
+                };
+                run_phase(io, opts, step, session, stats)?
+            }
+        };
+        match (outcome, relax.is_some()) {
+            (Outcome::Previous, false) if index > 1 => {
+                index -= 1;
+                relax = relax_after(&mut rng);
+            }
+            (Outcome::Previous, _) => relax = None,
+            (_, false) => {
+                relax = relax_after(&mut rng);
+                if relax.is_none() {
+                    index += 1;
+                }
+            }
+            (_, true) => {
+                index += 1;
+                relax = None;
+            }
+        }
+    }
+}
+
+/// Display one countdown per minute, `opts.num_reps` times, without any rest in between.
+/// `completed` is updated with the number of minutes completed, even if the user stops early.
+pub fn start_emom(io: &mut Io, opts: &Options, completed: &mut u32) -> Result<(), RepsError> {
+    let schedule = Schedule::emom(opts);
+    let mut session = Session::new(io, Some(schedule.total_duration()));
+    if opts.tui {
+        session.plan = plan(opts, schedule.phases.iter());
+    }
+    let mut i = 0;
+    while i < schedule.phases.len() {
+        let phase = &schedule.phases[i];
+        session.current.set(i);
+        let (label, color) = (phase.label(opts), phase.color(opts));
+        if let Phase::Rep { index, .. } = phase {
+            // The minutes before it, even when going back to it
+            *completed = index - 1;
+            ring_bell(io, opts, &session, 2);
+            play_sound(io, &session, &opts.sound_start);
+        }
+        phase.start(io, &label, i as u32 + 1);
+        let countdown = Countdown {
+            label: &label,
+            count: phase.duration(),
+            color: &color,
+        };
+        let outcome = run_countdown(io, opts, countdown, Some(&session), Controls::Countdown)?;
+        phase.end(io, i as u32 + 1, outcome);
+        if outcome == Outcome::Previous {
+            i = i.saturating_sub(1);
+            continue;
+        }
+        if let Phase::Rep { index, .. } = phase {
+            *completed = *index;
+        }
+        i += 1;
+    }
+    ring_fanfare(io, opts, &session);
+    Ok(())
+}
+
+/// Display the time counting up, without the pauses, until the user stops it.
+/// `elapsed` is updated with the time displayed.
+pub fn start_stopwatch(
+    io: &mut Io,
+    opts: &Options,
+    elapsed: &mut Duration,
+) -> Result<(), RepsError> {
+    let mut deadline = io.clock.now() + DURATION_1_SECOND;
+    io.output.event(&Event::Start {
+        phase: "stopwatch",
+        index: 1,
+        label: "Stopwatch",
+        duration: 0,
+    });
+    loop {
+        let message = format!(
+            "{}Stopwatch\n{}{}",
+            fg(opts, &color::Green),
+            fg(opts, &color::Blue),
+            format_duration(elapsed.as_secs())
+        );
+        let mut message = layout(io, opts, &message);
+        if opts.help_line && !opts.quiet {
+            let size = io.output.size();
+            let help = help_line(opts, Controls::Stopwatch, size.0);
+            message = pin_to_bottom(io, &message, &[help]);
+        }
+        io.output.render(&message);
+        io.output.event(&Event::Tick {
+            label: "Stopwatch",
+            elapsed: elapsed.as_secs() as u32,
+            remaining: None,
+        });
+        // The other requests are ignored, the screen is simply drawn again
+        let back_to = format!(
+            "Back to the stopwatch at {}",
+            format_duration(elapsed.as_secs())
+        );
+        let wake = wait(
+            io,
+            opts,
+            &mut deadline,
+            Controls::Stopwatch,
+            Displayed {
+                message: &message,
+                back_to: &back_to,
+            },
+            Running::default(),
+        )?;
+        if wake == Wake::Elapsed {
+            *elapsed += DURATION_1_SECOND;
+            deadline += DURATION_1_SECOND;
+        }
+    }
+}
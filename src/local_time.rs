@@ -0,0 +1,59 @@
+//! Time of the system in its time zone, e.g. to display the time of day with `--clock`.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Broken-down local time of `timestamp`, in seconds since the Unix epoch, or nothing if the
+/// system cannot tell it, e.g. for a year out of its range.
+fn local_time(timestamp: i64) -> Option<libc::tm> {
+    let time = timestamp as libc::time_t;
+    // SAFETY: `libc::tm` is made of integers and of a pointer that may be null, so all zeroes is
+    // a valid value.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the whole call. Unlike `localtime`, `localtime_r` does
+    // not use any static buffer, so it may be called from any thread.
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return None;
+    }
+    Some(tm)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64)
+}
+
+/// Hour and minute, e.g. "07:45"
+fn format_time_of_day(hour: i32, minute: i32) -> String {
+    format!("{:02}:{:02}", hour, minute)
+}
+
+/// Current time of day, e.g. "07:45"
+pub fn time_of_day() -> Option<String> {
+    local_time(now()).map(|tm| format_time_of_day(tm.tm_hour, tm.tm_min))
+}
+
+/// Offset of the local time from UTC at `timestamp`, in seconds, e.g. 3600 in Paris in winter
+pub fn utc_offset(timestamp: i64) -> Option<i64> {
+    local_time(timestamp).map(|tm| tm.tm_gmtoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_and_minutes_have_two_digits() {
+        assert_eq!(format_time_of_day(7, 5), "07:05");
+        assert_eq!(format_time_of_day(0, 0), "00:00");
+        assert_eq!(format_time_of_day(23, 59), "23:59");
+    }
+
+    #[test]
+    fn current_time_of_day() {
+        let time = time_of_day().unwrap();
+        let (hour, minute) = time.split_once(':').unwrap();
+        assert!(hour.len() == 2 && hour.parse::<u32>().unwrap() < 24);
+        assert!(minute.len() == 2 && minute.parse::<u32>().unwrap() < 60);
+    }
+}
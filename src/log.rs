@@ -0,0 +1,31 @@
+//! Diagnostics of the workout written on stderr with `--verbose`, e.g. to understand an issue with
+//! the timing or the keys. stdout is left to the screen, stderr is meant to be redirected to a
+//! file: `reps --verbose 5 30 2> reps.log`.
+
+use std::io::{stderr, Write};
+use std::time::Instant;
+
+/// Writes each message on its own line, with the time since the start of the program
+#[derive(Clone, Copy)]
+pub struct Log {
+    start: Instant,
+}
+
+impl Log {
+    pub fn new() -> Log {
+        Log {
+            start: Instant::now(),
+        }
+    }
+
+    pub fn write(&self, message: &str) {
+        let elapsed = self.start.elapsed();
+        let _ = writeln!(
+            stderr(),
+            "[{:>4}.{:03}] {}",
+            elapsed.as_secs(),
+            elapsed.subsec_millis(),
+            message
+        );
+    }
+}
@@ -1,136 +1,352 @@
 extern crate clap;
+extern crate libc;
+extern crate notify_rust;
+extern crate reps;
+extern crate serde;
+extern crate signal_hook;
 extern crate termion;
+extern crate toml;
 
-use std::io::{stdout, Bytes, Read};
-use std::thread;
-use std::time::Duration;
+mod cli;
+mod config;
+mod control;
+mod events;
+mod history;
+mod hooks;
+mod json;
+mod local_time;
+mod log;
+mod presets;
+mod resume;
+mod socket;
+mod sound;
+mod speech;
 
-use clap::{value_parser, Arg, Command};
+use std::cell::OnceCell;
+use std::io::{self, stdout, Bytes, Read, Write};
+use std::path::{Path, PathBuf};
+
+use cli::parse_args;
+use history::Record;
+use hooks::Hook;
+use local_time::time_of_day;
+use log::Log;
+use notify_rust::Notification;
+use reps::{
+    dry_run, format_duration, screen_size, start_emom, start_reps, start_stopwatch, wait_to_start,
+    Clock, Event, Input, Io, Keys, Mode, Output, RepsError, ScaledClock, Schedule, Stats,
+    SystemClock, TerminalGuard,
+};
+use resume::Checkpoint;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGWINCH};
+use socket::EventSocket;
+use sound::Player;
+use speech::Speaker;
 use std::process::exit;
-use termion::color::Color;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use termion::raw::IntoRawMode;
 use termion::{async_stdin, color, AsyncReader};
 
-const DURATION_500_MILLISECONDS: Duration = Duration::from_millis(500);
-const DURATION_1_SECOND: Duration = Duration::from_millis(1000);
-
-/// Display a single message on the screen, starting from the upper left.
-/// It will clear the screen and reset the text color at the end.
-fn print_message(message: &str) {
-    println!(
-        "{}{}{}{}",
-        termion::clear::All,
-        termion::cursor::Goto(1, 1),
-        message,
-        termion::color::Fg(color::Reset)
-    );
+/// Display the messages on the terminal, one at a time.
+#[derive(Default)]
+struct Screen {
+    /// Warnings that could not be displayed during the workout
+    warnings: Vec<String>,
+    /// Why the size of the terminal is unknown, if so. Only reported once, though it is asked for
+    /// every frame
+    size_unknown: OnceCell<String>,
+    player: Option<Player>,
+    speaker: Option<Speaker>,
+    no_color: bool,
+    /// Update a single line in place, instead of the whole screen
+    quiet: bool,
+    /// Print the events as JSON instead of drawing anything, stdout is left to them
+    json: bool,
+    /// Print the start of each phase on its own line instead of drawing the screen, with the time
+    /// since this instant
+    plain: Option<Instant>,
+    /// Command run at the start of each phase
+    hook: Option<Hook>,
+    /// Set when the terminal is resized (SIGWINCH)
+    resized: Arc<AtomicBool>,
+    /// Position in the workout, saved as it goes with `--resumable`
+    checkpoint: Option<Checkpoint>,
+    /// Whether the workout is paused, for the remote control
+    paused: Arc<AtomicBool>,
+    /// Socket the events are sent to, as JSON
+    socket: Option<EventSocket>,
+    /// Diagnostics of the workout, with `--verbose`
+    log: Option<Log>,
+    /// Display the time of day in the upper right corner, with `--clock`
+    time_of_day: bool,
 }
 
-/// Consume all the keys from the standard input. Is in charge of detecting if the user request to
-/// the program (ESC or Ctrl-C).
-///
-/// # Returns
-/// - if no keys were pressed, Ok(None)
-/// - If any key was pressed that should not exit the program, Ok(Some)
-/// - If a key was pressed that should stop the program (ESC, Ctrl-C), Err("Exiting")
-/// - If an error occurred, Err(<error message)
-fn consume_all_keystrokes(stdin: &mut Bytes<AsyncReader>) -> Result<Option<()>, String> {
-    let mut return_value = Ok(None);
-
-    loop {
-        match stdin.next() {
-            Some(e) => {
-                match e {
-                    Ok(27) => return Err("Exiting".to_string()), // ESC
-                    Ok(3) => return Err("Exiting".to_string()),  // Ctrl-C
-                    Err(e) => return Err(format!("Error: {}", e)),
-
-                    Ok(_) => return_value = Ok(Some(())),
-                }
+/// Keys pressed by the user. Once a signal requesting to stop the program is received (SIGTERM or
+/// SIGHUP), it is reported as an error instead, so that the workout stops and the terminal is
+/// restored as usual.
+struct Keyboard {
+    /// Read from the terminal, not from stdin. Not read in quiet mode, as the terminal is not in
+    /// raw mode
+    stdin: Option<Bytes<AsyncReader>>,
+    /// Keys sent with the remote control, if enabled
+    remote: Option<Receiver<io::Result<u8>>>,
+    /// Number of the signal received, 0 if none
+    signal: Arc<AtomicUsize>,
+    /// Diagnostics of the workout, with `--verbose`
+    log: Option<Log>,
+}
+
+impl Input for Keyboard {
+    fn next_key(&mut self) -> Option<io::Result<u8>> {
+        let key = self.read_key();
+        if let (Some(log), Some(key)) = (&self.log, &key) {
+            match key {
+                Ok(key) => log.write(&format!("Key {} ({})", key, Keys::name(*key))),
+                Err(e) => log.write(&format!("Keys not read: {}", e)),
             }
-            None => return return_value,
         }
+        key
     }
 }
 
-/// This method will check if keys were pressed since the last time it was called and will pause
-/// if that's the case until the user press another key.
-/// It will also forward requests to stop the program (and Errors).
-fn handle_pause(stdin: &mut Bytes<AsyncReader>) -> Result<(), String> {
-    if consume_all_keystrokes(stdin)?.is_some() {
-        print_message("PAUSE");
-        while consume_all_keystrokes(stdin)?.is_none() {
-            thread::sleep(DURATION_500_MILLISECONDS)
+impl Keyboard {
+    fn read_key(&mut self) -> Option<io::Result<u8>> {
+        match self.signal.load(Ordering::Relaxed) as i32 {
+            0 => match self.stdin.as_mut().and_then(|stdin| stdin.next_key()) {
+                Some(key) => Some(key),
+                None => self.remote.as_ref()?.try_recv().ok(),
+            },
+            SIGHUP => Some(Err(io::Error::other("stopped by SIGHUP"))),
+            SIGINT => Some(Err(io::Error::other("stopped by SIGINT"))),
+            _ => Some(Err(io::Error::other("stopped by SIGTERM"))),
         }
     }
-    Ok(())
 }
 
-/// Display a countdown with the specific label and colors.
-/// It will periodically check if the user entered any input and forward requests to stop the
-/// program as errors.
-fn countdown(
-    stdin: &mut Bytes<AsyncReader>,
-    label: &str,
-    count: u32,
-    color: &dyn Color,
-) -> Result<(), String> {
-    for sec in (1..=count).rev() {
-        print_message(
-            format!(
-                "{}{}\n{}{}s",
-                termion::color::Fg(color),
-                label,
-                termion::color::Fg(color::Blue),
-                sec
-            )
-            .as_str(),
+/// Escape sequence resetting the color, unless colors are disabled.
+fn reset_color(no_color: bool) -> String {
+    if no_color {
+        String::new()
+    } else {
+        termion::color::Fg(color::Reset).to_string()
+    }
+}
+
+/// Send a desktop notification.
+fn send_notification(message: &str) -> Result<(), String> {
+    Notification::new()
+        .summary("Reps")
+        .body(message)
+        .show()
+        .map(|_handle| ())
+        .map_err(|e| format!("Warning: could not send the notification: {}", e))
+}
+
+impl Output for Screen {
+    /// Display a single message on the screen, starting from the upper left.
+    /// It will clear the screen and reset the text color at the end.
+    fn render(&mut self, message: &str) {
+        if self.json || self.plain.is_some() {
+            return;
+        }
+        if self.quiet {
+            let lines: Vec<&str> = message.lines().map(str::trim).collect();
+            print!(
+                "\r{}{}{}",
+                termion::clear::CurrentLine,
+                lines.join(" ").trim(),
+                reset_color(self.no_color)
+            );
+            let _ = stdout().flush();
+            return;
+        }
+        println!(
+            "{}{}{}{}",
+            termion::clear::All,
+            termion::cursor::Goto(1, 1),
+            // In raw mode, a new line does not go back to the first column
+            message.replace('\n', "\r\n"),
+            reset_color(self.no_color)
         );
-        thread::sleep(DURATION_1_SECOND);
-        handle_pause(stdin.by_ref())?;
+        // Drawn again with each frame, at least once a second during the countdowns
+        if let Some(time) = time_of_day().filter(|_| self.time_of_day) {
+            let width = self.size().0;
+            print!(
+                "{}{}{}",
+                termion::cursor::Goto(width.saturating_sub(time.len() as u16) + 1, 1),
+                time,
+                termion::cursor::Goto(1, 1)
+            );
+            let _ = stdout().flush();
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        let (size, warning) = screen_size(termion::terminal_size());
+        // Nothing is drawn otherwise
+        if let Some(warning) = warning.filter(|_| !self.json && self.plain.is_none()) {
+            self.size_unknown.get_or_init(|| warning);
+        }
+        size
+    }
+
+    fn notify(&mut self, message: &str) {
+        if let Err(warning) = send_notification(message) {
+            // Only keep the first one, the following notifications will most likely fail as well
+            if self.warnings.is_empty() {
+                self.warnings.push(warning);
+            }
+        }
+    }
+
+    fn bell(&mut self) {
+        if self.json || self.plain.is_some() {
+            return;
+        }
+        print!("\x07");
+        let _ = stdout().flush();
+    }
+
+    fn play_sound(&mut self, path: &Path) {
+        if let Some(player) = &self.player {
+            player.play(path);
+        }
+    }
+
+    fn set_title(&mut self, title: &str) {
+        if self.json || self.plain.is_some() {
+            return;
+        }
+        print!("\x1b]0;{}\x07", title);
+    }
+
+    fn log(&mut self, message: &str) {
+        if let Some(log) = &self.log {
+            log.write(message);
+        }
+    }
+
+    fn resized(&mut self) -> bool {
+        self.resized.swap(false, Ordering::Relaxed)
+    }
+
+    fn speak(&mut self, text: &str) {
+        if let Some(speaker) = &mut self.speaker {
+            speaker.speak(text);
+        }
+    }
+
+    fn event(&mut self, event: &Event) {
+        if let Some(log) = &self.log {
+            match *event {
+                Event::Start {
+                    phase,
+                    index,
+                    label,
+                    duration,
+                } => log.write(&format!(
+                    "Start of {} {}: \"{}\", {}s",
+                    phase,
+                    index,
+                    label.replace('\n', " "),
+                    duration
+                )),
+                Event::Pause => log.write("Pause"),
+                Event::Resume => log.write("Resume"),
+                Event::Tick { .. } => {}
+            }
+        }
+        if self.json || self.socket.is_some() {
+            let line = events::event(event);
+            if self.json {
+                events::print(&line);
+            }
+            if let Some(socket) = &self.socket {
+                socket.send(&line);
+            }
+        }
+        if let Some(start) = self.plain {
+            let time = format_duration(start.elapsed().as_secs());
+            match *event {
+                Event::Start {
+                    label, duration, ..
+                } => println!(
+                    "[{}] {} ({})",
+                    time,
+                    label.replace('\n', " "),
+                    format_duration(duration as u64)
+                ),
+                Event::Pause => println!("[{}] Pause", time),
+                Event::Resume => println!("[{}] Resume", time),
+                Event::Tick { .. } => {}
+            }
+        }
+        match *event {
+            Event::Start {
+                phase,
+                index,
+                label,
+                duration,
+            } => {
+                if let Some(Err(warning)) =
+                    self.hook.as_mut().map(|hook| hook.run(phase, index, label))
+                {
+                    // Reported once, the command will most likely fail every time
+                    if !self.warnings.contains(&warning) {
+                        self.warnings.push(warning);
+                    }
+                }
+                if let Some(checkpoint) = &mut self.checkpoint {
+                    if checkpoint.start(phase, index, duration) {
+                        self.save_checkpoint();
+                    }
+                }
+            }
+            Event::Pause => self.paused.store(true, Ordering::Relaxed),
+            Event::Resume => self.paused.store(false, Ordering::Relaxed),
+            Event::Tick {
+                remaining: Some(remaining),
+                ..
+            } => {
+                if let Some(checkpoint) = &mut self.checkpoint {
+                    if checkpoint.tick(remaining) {
+                        self.save_checkpoint();
+                    }
+                }
+            }
+            Event::Tick { .. } => {}
+        }
     }
-    Ok(())
 }
 
-struct Options {
-    num_reps: u32,
-    rep_time: u32,
-    relax_time: u32,
+impl Screen {
+    fn save_checkpoint(&mut self) {
+        if let Some(Err(warning)) = self.checkpoint.as_mut().map(Checkpoint::save) {
+            // Reported once, the following checkpoints will most likely fail as well
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+    }
 }
 
-fn parse_args() -> Result<Options, clap::Error> {
-    let matches = Command::new("Reps")
-        .version("0.1.0")
-        .author("Simon M. <git@simon.marache.net>")
-        .arg(
-            Arg::new("num_reps")
-                .value_parser(value_parser!(u32))
-                .required(true),
-        )
-        .arg(
-            Arg::new("rep_time")
-                .value_parser(value_parser!(u32))
-                .required(true),
-        )
-        .arg(
-            Arg::new("relax_time")
-                .value_parser(value_parser!(u32))
-                .required(true),
-        )
-        .try_get_matches()?;
-
-    let num_reps = *matches.get_one::<u32>("num_reps").unwrap();
-    let rep_time = *matches.get_one::<u32>("rep_time").unwrap();
-    let relax_time = *matches.get_one::<u32>("relax_time").unwrap();
-    Ok(Options {
-        num_reps,
-        rep_time,
-        relax_time,
-    })
+/// Summary of the workout displayed at the end
+fn summary(stats: &Stats) -> String {
+    format!(
+        "{} reps completed\nWork: {}, rest: {}, total: {}\n{} pauses",
+        stats.reps,
+        format_duration(stats.work_time.as_secs()),
+        format_duration(stats.rest_time.as_secs()),
+        format_duration(stats.duration.as_secs()),
+        stats.pauses
+    )
 }
 
 fn main() {
-    let opts = match parse_args() {
+    let mut opts = match parse_args() {
         Err(e) => {
             println!("{}", e);
             exit(1);
@@ -138,51 +354,290 @@ fn main() {
         Ok(r) => r,
     };
 
-    let stdin = async_stdin().bytes();
+    if opts.dry_run {
+        print!("{}", dry_run(&opts));
+        return;
+    }
+    if opts.export {
+        if opts.mode == Mode::Stopwatch || opts.until_stop() {
+            println!("Only a workout with an end can be exported");
+            exit(1);
+        }
+        println!("{}", Schedule::from_options(&opts).encode());
+        return;
+    }
+    let checkpoint = if opts.resumable {
+        if opts.mode != Mode::Reps || opts.until_stop() {
+            println!("Only a workout with an end can be resumed");
+            exit(1);
+        }
+        // Planned once and saved, so that the random durations stay the same once resumed
+        opts.schedule = Some(Schedule::from_options(&opts));
+        match Checkpoint::new(&opts) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                println!("{}", e);
+                exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let paused = Arc::new(AtomicBool::new(false));
+    let remote = match opts.control_port {
+        Some(port) => match control::serve(port, paused.clone(), opts.keys) {
+            Ok(remote) => Some(remote),
+            Err(e) => {
+                println!("{}", e);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+    let socket = match &opts.socket {
+        Some(path) => match EventSocket::bind(path) {
+            Ok(socket) => Some(socket),
+            Err(e) => {
+                println!("{}", e);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let log = opts.verbose.then(Log::new);
     let stdout = stdout();
 
     // We need to be able to asynchronously check for input from the user, bypassing all the caching
     // and Control keys handling provided by the terminal. The only way is to put the terminal in
-    // raw mode
-    let stdout = stdout.lock().into_raw_mode().unwrap();
-    println!("{}{}", termion::clear::All, termion::cursor::Hide);
-
-    let result = start_reps(stdin, opts.num_reps, opts.rep_time, opts.relax_time);
-
-    // Bring the cursor back to a usable state
-    println!(
-        "{}{}{}{}",
-        termion::clear::All,
-        termion::cursor::Goto(1, 1),
-        termion::cursor::Show,
-        termion::color::Fg(color::Reset)
-    );
-
-    stdout
-        .suspend_raw_mode()
-        .expect("Error when reverting suspend mode");
-
-    let _ = result
-        .map(|_result| println!("done"))
-        .map_err(|error| println!("{}", error));
-}
+    // raw mode. In quiet and JSON modes the keys are not read, and the terminal is left as it is.
+    // Without a terminal, e.g. when the output is redirected to a file, only the phases are printed
+    let tty = termion::is_tty(&stdout);
+    let raw_mode = if opts.json || (opts.quiet && tty) {
+        Ok(None)
+    } else if tty {
+        stdout.lock().into_raw_mode().map(Some)
+    } else {
+        Err(io::Error::other("stdout is not a terminal"))
+    };
+    let (raw_mode, plain) = match raw_mode {
+        Ok(raw_mode) => (raw_mode, false),
+        Err(e) => {
+            eprintln!("Note: {}, the phases are printed one per line instead", e);
+            (None, true)
+        }
+    };
+
+    // The keys are read from the terminal rather than from stdin, which may be the plan piped with
+    // `--plan -`. They are disabled when there is no terminal to read them from
+    let keys = raw_mode.is_some() && termion::get_tty().is_ok();
+    if raw_mode.is_some() && !keys {
+        eprintln!("Note: there is no terminal to read the keys from, they are disabled");
+    }
+    let mut keyboard = Keyboard {
+        #[allow(clippy::unbuffered_bytes)] // Keys must be read as soon as they are available
+        stdin: keys.then(|| async_stdin().bytes()),
+        remote,
+        signal: Arc::new(AtomicUsize::new(0)),
+        log,
+    };
+    // SIGINT is only received when the terminal is not in raw mode, Ctrl-C is read as a key then
+    for signal in [SIGTERM, SIGHUP, SIGINT] {
+        if let Err(e) =
+            signal_hook::flag::register_usize(signal, keyboard.signal.clone(), signal as usize)
+        {
+            println!(
+                "Warning: the terminal will not be restored on signal {}: {}",
+                signal, e
+            );
+        }
+    }
+    let terminal = raw_mode.map(|raw_mode| TerminalGuard::new(raw_mode, &opts));
+
+    let mut screen = Screen {
+        no_color: opts.no_color,
+        quiet: opts.quiet,
+        json: opts.json,
+        plain: plain.then(Instant::now),
+        hook: opts.on_phase.as_deref().map(Hook::new),
+        paused,
+        checkpoint,
+        socket,
+        log,
+        time_of_day: opts.time_of_day,
+        ..Default::default()
+    };
+    let sounds: Vec<&PathBuf> = opts
+        .sound_start
+        .iter()
+        .chain(&opts.sound_end)
+        .chain(&opts.sound_done)
+        .collect();
+    if !sounds.is_empty() {
+        match Player::new() {
+            Ok(mut player) => {
+                for path in sounds {
+                    if let Err(warning) = player.load(path) {
+                        screen.warnings.push(warning);
+                    }
+                }
+                screen.player = Some(player);
+            }
+            Err(warning) => screen.warnings.push(warning),
+        }
+    }
+    if let Err(e) = signal_hook::flag::register(SIGWINCH, screen.resized.clone()) {
+        screen.warnings.push(format!(
+            "Warning: the screen will not be redrawn when the terminal is resized: {}",
+            e
+        ));
+    }
+    if opts.speak {
+        match Speaker::new() {
+            Ok(speaker) => screen.speaker = Some(speaker),
+            Err(warning) => screen.warnings.push(warning),
+        }
+    }
+    let scaled_clock;
+    let clock: &dyn Clock = if opts.speed != 1.0 {
+        scaled_clock = ScaledClock::new(opts.speed);
+        &scaled_clock
+    } else {
+        &SystemClock
+    };
+    let mut io = Io {
+        input: &mut keyboard,
+        output: &mut screen,
+        clock,
+    };
+    let mut completed_minutes = 0;
+    let mut stopwatch = Duration::from_secs(0);
+    let mut stats = Stats::default();
+    // The keys are only read in raw mode, there is nothing to wait for otherwise
+    if terminal.is_some() {
+        if let Err(error) = wait_to_start(&mut io, &opts) {
+            drop(terminal);
+            drop(screen.socket.take());
+            println!("{}", error);
+            exit(if matches!(error, RepsError::UserExit) {
+                0
+            } else {
+                1
+            });
+        }
+    }
+    let started = (SystemTime::now(), Instant::now());
+    let result = match opts.mode {
+        Mode::Reps => start_reps(&mut io, &opts, &mut stats),
+        Mode::Emom => start_emom(&mut io, &opts, &mut completed_minutes),
+        Mode::Stopwatch => start_stopwatch(&mut io, &opts, &mut stopwatch),
+    };
+
+    // Bring the terminal back to a usable state before printing the results
+    drop(terminal);
+    if opts.quiet {
+        // After the status line
+        println!();
+    }
+
+    // Only the events are printed on stdout in JSON mode
+    let report = |text: &str| {
+        if opts.json {
+            eprintln!("{}", text);
+        } else {
+            println!("{}", text);
+        }
+    };
+
+    for warning in screen.warnings.iter().chain(screen.size_unknown.get()) {
+        report(warning);
+    }
+
+    let (reps, duration) = match opts.mode {
+        Mode::Reps => {
+            report(&summary(&stats));
+            (stats.reps, stats.duration)
+        }
+        Mode::Emom => {
+            report(&format!(
+                "{}/{} minutes completed",
+                completed_minutes, opts.num_reps
+            ));
+            (completed_minutes, started.1.elapsed())
+        }
+        Mode::Stopwatch => {
+            report(&format!(
+                "Stopwatch: {}",
+                format_duration(stopwatch.as_secs())
+            ));
+            (0, stopwatch)
+        }
+    };
+    let end = events::end(&result, reps, duration.as_secs());
+    if opts.json {
+        events::print(&end);
+    }
+    // Removes the file of the socket, even when exiting with an error
+    if let Some(socket) = screen.socket.take() {
+        socket.send(&end);
+    }
+
+    if let Some(path) = &opts.log {
+        let record = Record {
+            timestamp: started
+                .0
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |timestamp| timestamp.as_secs()),
+            reps,
+            // Unknown when the reps go on until the user stops them
+            planned_reps: if opts.num_reps > 0 {
+                opts.num_reps
+            } else {
+                reps
+            },
+            rep_time: opts.rep_time,
+            relax_time: opts.relax_time,
+            duration: duration.as_secs(),
+            completed: result.is_ok(),
+            exercise: opts
+                .exercises()
+                .iter()
+                .map(|exercise| exercise.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        if let Err(warning) = history::append(path, &record) {
+            report(&warning);
+        }
+    }
 
-/// Display the countdowns using the values provided by the user
-fn start_reps(
-    mut stdin: Bytes<AsyncReader>,
-    reps: u32,
-    time: u32,
-    time_between_reps: u32,
-) -> Result<(), String> {
-    countdown(&mut stdin, "Starting in", 3, &color::Blue)?;
-    for rep in 1..=reps {
-        countdown(
-            &mut stdin,
-            format!("Rep {}/{}", rep, reps).as_str(),
-            time,
-            &color::Red,
-        )?;
-        countdown(&mut stdin, "Relax!", time_between_reps, &color::Green)?;
-    }
-    Ok(())
+    match result {
+        Ok(()) => {
+            report("done");
+            if opts.resumable {
+                if let Err(warning) = resume::clear() {
+                    report(&warning);
+                }
+            }
+            if let Err(warning) = presets::save_last(&opts) {
+                report(&format!(
+                    "Warning: the workout will not be repeated with --repeat-last: {}",
+                    warning
+                ));
+            }
+            if opts.notify {
+                let message = format!("Workout complete: {} reps done", reps);
+                if let Err(warning) = send_notification(&message) {
+                    report(&warning);
+                }
+            }
+        }
+        // Stopping on time is not a failure
+        Err(error @ (RepsError::UserExit | RepsError::TimeCap)) => report(&error.to_string()),
+        Err(error) => {
+            report(&error.to_string());
+            exit(1);
+        }
+    }
 }
@@ -0,0 +1,118 @@
+//! Workouts saved with `--save NAME`, and run again with `--preset NAME`. Each preset is a TOML
+//! file in the configuration directory of the user, e.g. `~/.config/reps/presets/NAME.toml`.
+//! The last workout completed is saved the same way in the state directory, to be run again with
+//! `--repeat-last`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+use reps::Options;
+
+const EXTENSION: &str = "toml";
+
+fn presets_dir() -> Result<PathBuf, String> {
+    ProjectDirs::from("", "", "reps")
+        .map(|dirs| dirs.config_dir().join("presets"))
+        .ok_or_else(|| "Could not find the configuration directory".to_string())
+}
+
+/// Only some platforms have a state directory, the local data directory is used otherwise
+fn last_path() -> Result<PathBuf, String> {
+    ProjectDirs::from("", "", "reps")
+        .map(|dirs| {
+            dirs.state_dir()
+                .unwrap_or_else(|| dirs.data_local_dir())
+                .join("last")
+                .with_extension(EXTENSION)
+        })
+        .ok_or_else(|| "Could not find the state directory".to_string())
+}
+
+fn write(path: &Path, opts: &Options) -> Result<(), String> {
+    let content =
+        toml::to_string(opts).map_err(|e| format!("Could not save {}: {}", path.display(), e))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+fn read(path: &Path) -> Result<Options, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    toml::from_str(&content)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e.to_string().trim_end()))
+}
+
+/// The name is used as a file name, so it must not be able to point anywhere else
+fn preset_path(name: &str) -> Result<PathBuf, String> {
+    let valid = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || !name.chars().all(valid) {
+        return Err(format!(
+            "Invalid preset name \"{}\": use only letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    Ok(presets_dir()?.join(name).with_extension(EXTENSION))
+}
+
+/// Names of the saved presets, in alphabetical order.
+pub fn list() -> Result<Vec<String>, String> {
+    let dir = presets_dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        // Nothing has been saved yet
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Could not read {}: {}", dir.display(), e)),
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == EXTENSION)
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Save the options under `name`, replacing the preset with the same name if any.
+pub fn save(name: &str, opts: &Options) -> Result<(), String> {
+    write(&preset_path(name)?, opts)
+}
+
+/// Load the options saved under `name`.
+pub fn load(name: &str) -> Result<Options, String> {
+    let path = preset_path(name)?;
+    if !path.exists() {
+        let names = list()?;
+        return Err(if names.is_empty() {
+            format!("No preset named \"{}\", save one with --save", name)
+        } else {
+            format!(
+                "No preset named \"{}\", the available presets are: {}",
+                name,
+                names.join(", ")
+            )
+        });
+    }
+    read(&path)
+}
+
+/// Remember the options of the workout, for `--repeat-last`.
+pub fn save_last(opts: &Options) -> Result<(), String> {
+    write(&last_path()?, opts)
+}
+
+/// Load the options of the last workout completed.
+pub fn load_last() -> Result<Options, String> {
+    let path = last_path()?;
+    if !path.exists() {
+        return Err("Nothing to repeat: no workout has been completed yet".to_string());
+    }
+    // Whatever happened to the file, there is no workout to repeat
+    read(&path).map_err(|e| format!("Nothing to repeat: {}", e))
+}
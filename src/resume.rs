@@ -0,0 +1,176 @@
+//! Workouts run with `--resumable`, whose position is saved in the state directory as they go, so
+//! that they can be resumed with `--resume` once interrupted, e.g. after a crash. The state is
+//! cleared once the workout is completed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use directories::ProjectDirs;
+use reps::Options;
+use serde::{Deserialize, Serialize};
+
+/// Version of the format of the state, which cannot be resumed by another version
+const VERSION: u32 = 1;
+/// Age of a state after which the workout was most likely given up rather than interrupted
+pub const STALE_AFTER: u64 = 12 * 60 * 60;
+/// Interval between two checkpoints during a countdown, in seconds
+const INTERVAL: u32 = 10;
+
+/// Only some platforms have a state directory, the local data directory is used otherwise
+fn state_path() -> Result<PathBuf, String> {
+    ProjectDirs::from("", "", "reps")
+        .map(|dirs| {
+            dirs.state_dir()
+                .unwrap_or_else(|| dirs.data_local_dir())
+                .join("resume.toml")
+        })
+        .ok_or_else(|| "Could not find the state directory".to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+/// Position in a workout, saved along with the options of the workout
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    version: u32,
+    /// When it was saved, in seconds since the epoch
+    saved_at: u64,
+    /// Phase of the schedule being run, from 0
+    phase: u32,
+    /// Seconds remaining in the phase
+    remaining: u32,
+    /// Options of the workout, including its whole schedule
+    workout: toml::Value,
+    /// Whether the countdown running is one of the schedule, rather than the prep added when the
+    /// workout is resumed
+    #[serde(skip)]
+    scheduled: bool,
+}
+
+impl Checkpoint {
+    /// Checkpoint at the start of the workout, or where it is resumed. Its schedule must be set.
+    pub fn new(opts: &Options) -> Result<Checkpoint, String> {
+        let (phase, remaining) = opts.resume_at.unwrap_or((0, 0));
+        Ok(Checkpoint {
+            version: VERSION,
+            saved_at: now(),
+            phase,
+            remaining,
+            workout: toml::Value::try_from(opts)
+                .map_err(|e| format!("The workout cannot be made resumable: {}", e))?,
+            scheduled: true,
+        })
+    }
+
+    /// Go on with the `index`-th phase of the workout (from 1) of the given `kind`, e.g. "rep".
+    /// Returns whether the checkpoint changed.
+    pub fn start(&mut self, kind: &str, index: u32, duration: u32) -> bool {
+        // Only the first phase of a workout is a prep, the others are added when resuming it
+        self.scheduled = kind != "prep" || index == 1;
+        if self.scheduled {
+            self.phase = index - 1;
+            self.remaining = duration;
+        }
+        self.scheduled
+    }
+
+    /// Record the seconds remaining in the phase. Returns whether the checkpoint must be saved.
+    pub fn tick(&mut self, remaining: u32) -> bool {
+        if self.scheduled {
+            self.remaining = remaining;
+        }
+        self.scheduled && remaining.is_multiple_of(INTERVAL)
+    }
+
+    /// Seconds since the checkpoint was saved
+    pub fn age(&self) -> u64 {
+        now().saturating_sub(self.saved_at)
+    }
+
+    /// Options to go on with the workout from the checkpoint, or from its start if `again`.
+    pub fn options(&self, again: bool) -> Result<Options, String> {
+        let opts: Options = self
+            .workout
+            .clone()
+            .try_into()
+            .map_err(|e| invalid(&e.to_string()))?;
+        let phases = opts
+            .schedule
+            .as_ref()
+            .map_or(0, |schedule| schedule.phases.len());
+        if phases == 0 {
+            return Err(invalid("its schedule is missing"));
+        }
+        if !again && (self.phase as usize >= phases || self.remaining == 0) {
+            return Err(
+                "The interrupted workout cannot be resumed: its position is not in the schedule"
+                    .to_string(),
+            );
+        }
+        Ok(Options {
+            resume_at: (!again).then_some((self.phase, self.remaining)),
+            ..opts
+        })
+    }
+
+    /// Write the checkpoint, replacing the previous one only once it is complete.
+    pub fn save(&mut self) -> Result<(), String> {
+        self.saved_at = now();
+        let path = state_path()?;
+        let content =
+            toml::to_string(self).map_err(|e| format!("Could not save the position: {}", e))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+        }
+        let partial = path.with_extension("toml.partial");
+        fs::write(&partial, content)
+            .and_then(|()| fs::rename(&partial, &path))
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+}
+
+fn invalid(reason: &str) -> String {
+    format!(
+        "The interrupted workout cannot be resumed: {}. Run it again with --resumable to start \
+         fresh",
+        reason.trim_end()
+    )
+}
+
+fn read(path: &Path) -> Result<Checkpoint, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let checkpoint: Checkpoint = toml::from_str(&content).map_err(|e| invalid(&e.to_string()))?;
+    if checkpoint.version != VERSION {
+        return Err(invalid("it was saved by another version of reps"));
+    }
+    Ok(checkpoint)
+}
+
+/// Load the position of the workout that was interrupted, if any.
+pub fn load() -> Result<Checkpoint, String> {
+    let path = state_path()?;
+    if !path.exists() {
+        return Err(
+            "Nothing to resume: no workout run with --resumable was interrupted".to_string(),
+        );
+    }
+    read(&path)
+}
+
+/// Forget the position of the workout, once it is completed.
+pub fn clear() -> Result<(), String> {
+    let path = state_path()?;
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Could not remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,435 @@
+//! Plan of the workout: every countdown, in order, built before the workout starts.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{check_duration, format_duration, parse_duration, Mode, Options};
+
+/// Part of the workout, displayed as a single countdown
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Phase {
+    Prep(u32),
+    Warmup(u32),
+    Rep {
+        exercise: String,
+        /// Position of the rep in the exercise, from 1 to `total`, which is 0 when the reps go on
+        /// until the user stops them
+        index: u32,
+        total: u32,
+        /// Current set and number of sets
+        set: (u32, u32),
+        duration: u32,
+    },
+    Relax(u32),
+    SetRest(u32),
+    Cooldown(u32),
+}
+
+impl Phase {
+    /// Planned duration, in seconds
+    pub fn duration(&self) -> u32 {
+        match *self {
+            Phase::Prep(duration)
+            | Phase::Warmup(duration)
+            | Phase::Relax(duration)
+            | Phase::SetRest(duration)
+            | Phase::Cooldown(duration)
+            | Phase::Rep { duration, .. } => duration,
+        }
+    }
+
+    fn set_duration(&mut self, seconds: u32) {
+        match self {
+            Phase::Prep(duration)
+            | Phase::Warmup(duration)
+            | Phase::Relax(duration)
+            | Phase::SetRest(duration)
+            | Phase::Cooldown(duration)
+            | Phase::Rep { duration, .. } => *duration = seconds,
+        }
+    }
+}
+
+/// All the phases of the workout, in order
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Schedule {
+    pub phases: Vec<Phase>,
+}
+
+impl Schedule {
+    /// Plan the workout described by the options, unless they already give its schedule. The
+    /// duration of the relax is picked at this point when it is random. The stopwatch has no
+    /// plan, it goes on until the user stops it, and neither have the reps that go on until then,
+    /// only what comes before them is planned.
+    pub fn from_options(opts: &Options) -> Schedule {
+        if let Some(schedule) = &opts.schedule {
+            return schedule.clone();
+        }
+        match opts.mode {
+            Mode::Reps => Schedule::reps(opts),
+            Mode::Emom => Schedule::emom(opts),
+            Mode::Stopwatch => Schedule::default(),
+        }
+    }
+
+    /// Plan one rep per minute, without any rest, whatever the mode of the options
+    pub fn emom(opts: &Options) -> Schedule {
+        let mut phases = vec![];
+        if opts.prep_time > 0 {
+            phases.push(Phase::Prep(opts.prep_time));
+        }
+        for minute in 1..=opts.num_reps {
+            phases.push(Phase::Rep {
+                exercise: "Minute".to_string(),
+                index: minute,
+                total: opts.num_reps,
+                set: (1, 1),
+                duration: opts.rep_time,
+            });
+        }
+        Schedule { phases }
+    }
+
+    fn reps(opts: &Options) -> Schedule {
+        let mut rng = rng(opts);
+        let mut exercises = opts.exercises();
+        if opts.shuffle {
+            exercises.shuffle(&mut rng);
+        }
+        let mut phases = vec![];
+        if opts.prep_time > 0 {
+            phases.push(Phase::Prep(opts.prep_time));
+        }
+        if opts.warmup > 0 {
+            phases.push(Phase::Warmup(opts.warmup));
+        }
+        let sets = opts.sets.max(1);
+        for set in 1..=sets {
+            if set > 1 {
+                phases.push(Phase::SetRest(opts.set_rest));
+            }
+            for exercise in &exercises {
+                for index in 1..=exercise.reps {
+                    phases.push(Phase::Rep {
+                        exercise: exercise.name.clone(),
+                        index,
+                        total: exercise.reps,
+                        set: (set, sets),
+                        duration: opts
+                            .rep_times
+                            .get(index as usize - 1)
+                            .copied()
+                            .unwrap_or(exercise.rep_time),
+                    });
+                    let relax = if opts.long_break_every > 0 && index % opts.long_break_every == 0 {
+                        opts.long_break
+                    } else if let Some(&relax) = opts.relax_times.get(index as usize - 1) {
+                        relax
+                    } else {
+                        relax(opts, exercise.relax_time, &mut rng)
+                    };
+                    // Back-to-back reps
+                    if relax > 0 {
+                        phases.push(Phase::Relax(relax));
+                    }
+                }
+            }
+        }
+        // The workout is over with the last rep, the random relaxes stay the same with a seed
+        if !opts.trailing_rest && matches!(phases.last(), Some(Phase::Relax(_))) {
+            phases.pop();
+        }
+        if opts.cooldown > 0 && !opts.until_stop() {
+            phases.push(Phase::Cooldown(opts.cooldown));
+        }
+        Schedule { phases }
+    }
+
+    /// Planned duration of the whole workout, in seconds
+    pub fn total_duration(&self) -> u64 {
+        self.phases
+            .iter()
+            .map(|phase| phase.duration() as u64)
+            .sum()
+    }
+
+    /// Number of reps, time spent working and resting, and planned duration of the workout, e.g.
+    /// to check that the rep and relax times were not swapped before it starts
+    pub fn summary(&self) -> String {
+        let (mut reps, mut work, mut rest) = (0, 0, 0);
+        for phase in &self.phases {
+            match phase {
+                Phase::Rep { duration, .. } => {
+                    reps += 1;
+                    work += *duration as u64;
+                }
+                Phase::Relax(duration) | Phase::SetRest(duration) => rest += *duration as u64,
+                _ => (),
+            }
+        }
+        format!(
+            "{} rep{}\nWork {}, rest {}\nTotal {}",
+            reps,
+            if reps == 1 { "" } else { "s" },
+            format_duration(work),
+            format_duration(rest),
+            format_duration(self.total_duration())
+        )
+    }
+
+    /// Each phase with its position in the workout, from 1
+    pub(crate) fn numbered(&self) -> Vec<(u32, Phase)> {
+        (1..).zip(self.phases.iter().cloned()).collect()
+    }
+
+    /// Phases left when the workout is resumed at the `phase`-th one (from 0) with `remaining`
+    /// seconds in it, with their position in the whole workout. A prep countdown comes first,
+    /// numbered as the phase it prepares for, unless the workout is resumed during its own prep.
+    pub(crate) fn resumed(
+        &self,
+        opts: &Options,
+        (phase, remaining): (u32, u32),
+    ) -> Vec<(u32, Phase)> {
+        let mut phases: Vec<(u32, Phase)> = self.numbered().split_off(phase as usize);
+        if let Some((index, first)) = phases.first_mut() {
+            first.set_duration(remaining);
+            if opts.prep_time > 0 && !matches!(first, Phase::Prep(_)) {
+                let index = *index;
+                phases.insert(0, (index, Phase::Prep(opts.prep_time)));
+            }
+        }
+        phases
+    }
+
+    /// Short text describing the schedule, which can be shared and decoded with `decode`, e.g.
+    /// "1,P5,Rep:30,R10,30,R10": the version of the format, then one item per phase. The first
+    /// rep of an exercise is given with its name, the following ones only with their duration,
+    /// their position and the set are found again from the order of the phases.
+    pub fn encode(&self) -> String {
+        let mut items = vec![CODE_VERSION.to_string()];
+        for phase in &self.phases {
+            items.push(match phase {
+                Phase::Prep(duration) => format!("P{}", duration),
+                Phase::Warmup(duration) => format!("W{}", duration),
+                Phase::Relax(duration) => format!("R{}", duration),
+                Phase::SetRest(duration) => format!("S{}", duration),
+                Phase::Cooldown(duration) => format!("C{}", duration),
+                Phase::Rep {
+                    exercise,
+                    index: 1,
+                    duration,
+                    ..
+                } => format!("{}:{}", escape(exercise), duration),
+                Phase::Rep { duration, .. } => duration.to_string(),
+            });
+        }
+        items.join(",")
+    }
+
+    /// Build the schedule described by the text written by `encode`.
+    pub fn decode(code: &str) -> Result<Schedule, String> {
+        let invalid = |e: String| format!("Invalid workout \"{}\": {}", code, e);
+        let mut items = code.trim().split(',');
+        match items.next() {
+            Some(CODE_VERSION) => {}
+            _ => {
+                return Err(invalid(
+                    "it was not exported by this version of reps".to_string(),
+                ))
+            }
+        }
+        let mut phases = vec![];
+        // Current set, and position of the reps of the current exercise
+        let (mut set, mut reps) = (1, vec![]);
+        for item in items {
+            // Nothing would be done with a rep of 0s
+            let duration = |text: &str, work: bool| {
+                text.parse::<u32>()
+                    .map_err(|_| invalid(format!("\"{}\" is not a phase", item)))
+                    .and_then(|duration| check_duration(text, duration, work).map_err(invalid))
+            };
+            let (kind, text) = item.split_at(item.chars().next().map_or(0, char::len_utf8));
+            let phase = match (item.split_once(':'), kind) {
+                // The first rep of an exercise
+                (Some((name, text)), _) => {
+                    set_totals(&mut phases, &mut reps);
+                    let exercise = unescape(name).map_err(invalid)?;
+                    rep(&phases, &mut reps, exercise, set, duration(text, true)?)
+                }
+                (None, "P") => Phase::Prep(duration(text, false)?),
+                (None, "W") => Phase::Warmup(duration(text, false)?),
+                (None, "R") => Phase::Relax(duration(text, false)?),
+                (None, "C") => Phase::Cooldown(duration(text, false)?),
+                (None, "S") => {
+                    set_totals(&mut phases, &mut reps);
+                    set += 1;
+                    Phase::SetRest(duration(text, false)?)
+                }
+                // One of the following reps, of the same exercise
+                (None, _) => {
+                    let duration = duration(item, true)?;
+                    match reps.first().map(|&first| &phases[first]) {
+                        Some(Phase::Rep { exercise, .. }) => {
+                            let exercise = exercise.clone();
+                            rep(&phases, &mut reps, exercise, set, duration)
+                        }
+                        _ => return Err(invalid(format!("\"{}\" has no exercise", item))),
+                    }
+                }
+            };
+            phases.push(phase);
+        }
+        set_totals(&mut phases, &mut reps);
+        if phases.is_empty() {
+            return Err(invalid("there is nothing to do".to_string()));
+        }
+        for phase in &mut phases {
+            if let Phase::Rep { set: (_, sets), .. } = phase {
+                *sets = set;
+            }
+        }
+        Ok(Schedule { phases })
+    }
+
+    /// Build the schedule listed in a plan, one phase per line as its kind and its duration, e.g.
+    /// "rep:30" then "rest:1m". The kinds are prep, warmup, rep, rest and cooldown. The empty
+    /// lines, and the ones starting with '#', are ignored.
+    pub fn from_plan(plan: &str) -> Result<Schedule, String> {
+        let (mut phases, mut reps) = (vec![], vec![]);
+        for (number, line) in plan.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |e: String| format!("Invalid plan, line {}: {}", number + 1, e);
+            let (kind, text) = line
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("\"{}\" is not a phase, e.g. \"rep:30\"", line)))?;
+            let (kind, text) = (kind.trim(), text.trim());
+            // Nothing would be done with a rep of 0s
+            let duration = parse_duration(text)
+                .and_then(|duration| check_duration(text, duration, kind == "rep"))
+                .map_err(invalid)?;
+            let phase = match kind {
+                "prep" => Phase::Prep(duration),
+                "warmup" => Phase::Warmup(duration),
+                "rep" => rep(&phases, &mut reps, "Rep".to_string(), 1, duration),
+                "rest" => Phase::Relax(duration),
+                "cooldown" => Phase::Cooldown(duration),
+                kind => {
+                    return Err(invalid(format!(
+                        "\"{}\" is not a kind of phase: prep, warmup, rep, rest or cooldown",
+                        kind
+                    )))
+                }
+            };
+            phases.push(phase);
+        }
+        set_totals(&mut phases, &mut reps);
+        if phases.is_empty() {
+            return Err("Invalid plan: there is nothing to do".to_string());
+        }
+        for phase in &mut phases {
+            if let Phase::Rep { set, .. } = phase {
+                *set = (1, 1);
+            }
+        }
+        Ok(Schedule { phases })
+    }
+}
+
+/// Version of the format written by `Schedule::encode`
+const CODE_VERSION: &str = "1";
+
+/// Next rep of the exercise, at the end of the `phases`. It is added to the positions of the `reps`
+/// of the exercise, the number of reps is set once they are all known.
+fn rep(
+    phases: &[Phase],
+    reps: &mut Vec<usize>,
+    exercise: String,
+    set: u32,
+    duration: u32,
+) -> Phase {
+    reps.push(phases.len());
+    Phase::Rep {
+        exercise,
+        index: reps.len() as u32,
+        total: 0,
+        set: (set, 0),
+        duration,
+    }
+}
+
+/// Set the number of reps of the exercise in each of them, once they are all known.
+fn set_totals(phases: &mut [Phase], reps: &mut Vec<usize>) {
+    let count = reps.len() as u32;
+    for index in reps.drain(..) {
+        if let Phase::Rep { total, .. } = &mut phases[index] {
+            *total = count;
+        }
+    }
+}
+
+/// Replace the characters that have a meaning in the encoded schedule, or could not be shared
+/// as they are, with "%" and their code in hexadecimal
+fn escape(name: &str) -> String {
+    let mut escaped = String::new();
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+fn unescape(name: &str) -> Result<String, String> {
+    let invalid = || format!("\"{}\" is not a valid name", name);
+    let mut bytes = vec![];
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let code = tail.get(..2).ok_or_else(invalid)?;
+            let code = std::str::from_utf8(code).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(code, 16).map_err(|_| invalid())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Source of the random choices of the workout, the same ones every time if a seed is given
+pub(crate) fn rng(opts: &Options) -> StdRng {
+    match opts.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Duration of the relax after a rep, picked in `relax_range` when there is one
+pub(crate) fn relax(opts: &Options, relax_time: u32, rng: &mut impl Rng) -> u32 {
+    match opts.relax_range {
+        Some((min, max)) => rng.gen_range(min..=max),
+        None => relax_time,
+    }
+}
+
+/// Rep number `index` of the reps that go on until the user stops them
+pub(crate) fn endless_rep(opts: &Options, index: u32) -> Phase {
+    Phase::Rep {
+        exercise: opts.rep_label.clone().unwrap_or_else(|| "Rep".to_string()),
+        index,
+        total: 0,
+        set: (1, 1),
+        duration: opts.rep_time,
+    }
+}
@@ -0,0 +1,189 @@
+//! Events of the workout sent to the programs connected to a Unix domain socket, e.g. a status
+//! bar widget:
+//!
+//! ```sh
+//! socat - UNIX-CONNECT:/tmp/reps.sock
+//! ```
+//!
+//! Each client receives the events as JSON, one per line, from the moment it connects.
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Socket accepting the clients on a background thread. The file of the socket is removed when
+/// it is dropped.
+pub struct EventSocket {
+    path: PathBuf,
+    clients: Arc<Mutex<Vec<UnixStream>>>,
+}
+
+impl EventSocket {
+    /// Create the socket at `path`. A socket left there by a previous workout is replaced, unless
+    /// a program still listens on it.
+    pub fn bind(path: &Path) -> Result<EventSocket, String> {
+        let error = |e: std::io::Error| format!("Could not listen on {}: {}", path.display(), e);
+        if let Ok(metadata) = fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() || UnixStream::connect(path).is_ok() {
+                return Err(error(ErrorKind::AddrInUse.into()));
+            }
+            fs::remove_file(path).map_err(error)?;
+        }
+        let listener = UnixListener::bind(path).map_err(error)?;
+        let clients = Arc::new(Mutex::new(vec![]));
+        let accepted = clients.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // The timer must not wait for a client that does not read its events
+                if stream.set_nonblocking(true).is_ok() {
+                    if let Ok(mut clients) = accepted.lock() {
+                        clients.push(stream);
+                    }
+                }
+            }
+        });
+        Ok(EventSocket {
+            path: path.to_path_buf(),
+            clients,
+        })
+    }
+
+    /// Send the line of an event to all the clients. The ones that disconnected, or that cannot
+    /// keep up, are dropped.
+    pub fn send(&self, line: &str) {
+        if let Ok(mut clients) = self.clients.lock() {
+            let line = format!("{}\n", line);
+            clients.retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+        }
+    }
+}
+
+impl Drop for EventSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events;
+    use crate::json::parse_object;
+    use reps::{start_reps, Clock, Event, Input, Io, Options, Output, Stats};
+    use std::cell::Cell;
+    use std::io::{BufRead, BufReader};
+    use std::time::{Duration, Instant};
+
+    /// Clock that advances instantly when sleeping.
+    struct FakeClock {
+        start: Instant,
+        elapsed: Cell<Duration>,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.start + self.elapsed.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.elapsed.set(self.elapsed.get() + duration)
+        }
+    }
+
+    struct NoKeys;
+
+    impl Input for NoKeys {
+        fn next_key(&mut self) -> Option<std::io::Result<u8>> {
+            None
+        }
+    }
+
+    /// Output sending the events to the socket, as the timer does.
+    struct Sender<'a>(&'a EventSocket);
+
+    impl Output for Sender<'_> {
+        fn render(&mut self, _message: &str) {}
+
+        fn event(&mut self, event: &Event) {
+            self.0.send(&events::event(event));
+        }
+    }
+
+    #[test]
+    fn events_of_a_workout() {
+        let dir = std::env::temp_dir().join(format!("reps-socket-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("reps.sock");
+        let socket = EventSocket::bind(&path).unwrap();
+        // A second workout cannot take the socket over
+        assert!(EventSocket::bind(&path).is_err());
+        let client = UnixStream::connect(&path).unwrap();
+        // Along with the connection made by the second workout
+        while socket.clients.lock().unwrap().len() < 2 {
+            thread::yield_now();
+        }
+
+        let opts = Options {
+            num_reps: 2,
+            rep_time: 1,
+            relax_time: 1,
+            prep_time: 0,
+            ..Default::default()
+        };
+        let clock = FakeClock {
+            start: Instant::now(),
+            elapsed: Cell::new(Duration::from_secs(0)),
+        };
+        let result = start_reps(
+            &mut Io {
+                input: &mut NoKeys,
+                output: &mut Sender(&socket),
+                clock: &clock,
+            },
+            &opts,
+            &mut Stats::default(),
+        );
+        socket.send(&events::end(&result, 2, 4));
+        drop(socket);
+        assert!(!path.exists());
+
+        let value = |fields: &[(String, String)], name: &str| {
+            let field = fields.iter().find(|(field, _)| field == name);
+            field.map(|(_, value)| value.clone()).unwrap_or_default()
+        };
+        let mut lines = vec![];
+        for line in BufReader::new(client).lines() {
+            let fields = parse_object(&line.unwrap()).unwrap();
+            let end = value(&fields, "event") == "end";
+            lines.push(fields);
+            if end {
+                break;
+            }
+        }
+        let starts: Vec<(String, String)> = lines
+            .iter()
+            .filter(|fields| value(fields, "event") == "start")
+            .map(|fields| (value(fields, "phase"), value(fields, "label")))
+            .collect();
+        let start = |phase: &str, label: &str| (phase.to_string(), label.to_string());
+        assert_eq!(
+            starts,
+            [
+                start("rep", "Rep 1/2"),
+                start("relax", "Relax!"),
+                start("rep", "Rep 2/2")
+            ]
+        );
+        assert!(lines
+            .iter()
+            .all(|fields| value(fields, "timestamp").parse::<u128>().is_ok()));
+        let last = lines.last().unwrap();
+        assert_eq!(value(last, "completed"), "true");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+//! Playback of the sound files provided by the user. It relies on `rodio`, which is only built
+//! with the `sound` feature as it needs the system audio libraries (e.g. ALSA on Linux).
+
+use std::path::Path;
+
+#[cfg(feature = "sound")]
+use std::collections::HashMap;
+#[cfg(feature = "sound")]
+use std::io::Cursor;
+#[cfg(feature = "sound")]
+use std::path::PathBuf;
+#[cfg(feature = "sound")]
+use std::sync::Arc;
+
+#[cfg(feature = "sound")]
+pub struct Player {
+    // The sounds are only played as long as the stream is alive
+    _stream: rodio::OutputStream,
+    handle: rodio::OutputStreamHandle,
+    sounds: HashMap<PathBuf, Arc<[u8]>>,
+}
+
+#[cfg(feature = "sound")]
+impl Player {
+    /// Open the default audio device.
+    pub fn new() -> Result<Player, String> {
+        let (stream, handle) = rodio::OutputStream::try_default()
+            .map_err(|e| format!("Warning: no audio device available: {}", e))?;
+        Ok(Player {
+            _stream: stream,
+            handle,
+            sounds: HashMap::new(),
+        })
+    }
+
+    /// Read and decode the sound file once, so that any issue is reported before the workout
+    /// starts and the file is not read again every time it is played.
+    pub fn load(&mut self, path: &Path) -> Result<(), String> {
+        let data: Arc<[u8]> = std::fs::read(path)
+            .map_err(|e| format!("Warning: could not read {}: {}", path.display(), e))?
+            .into();
+        rodio::Decoder::new(Cursor::new(data.clone()))
+            .map_err(|e| format!("Warning: could not decode {}: {}", path.display(), e))?;
+        self.sounds.insert(path.to_path_buf(), data);
+        Ok(())
+    }
+
+    /// Start playing the sound, if it was loaded. The playback happens on the audio thread.
+    pub fn play(&self, path: &Path) {
+        if let Some(data) = self.sounds.get(path) {
+            let sink = rodio::Sink::try_new(&self.handle);
+            let source = rodio::Decoder::new(Cursor::new(data.clone()));
+            if let (Ok(sink), Ok(source)) = (sink, source) {
+                sink.append(source);
+                sink.detach();
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "sound"))]
+pub struct Player;
+
+#[cfg(not(feature = "sound"))]
+impl Player {
+    pub fn new() -> Result<Player, String> {
+        Err("Warning: reps was built without sound support (see the `sound` feature)".to_string())
+    }
+
+    pub fn load(&mut self, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn play(&self, _path: &Path) {}
+}
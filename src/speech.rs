@@ -0,0 +1,55 @@
+//! Announcements spoken with the text-to-speech program of the platform: `say` on macOS,
+//! `espeak-ng` or `espeak` elsewhere.
+
+use std::env;
+use std::process::{Child, Command, Stdio};
+
+#[cfg(target_os = "macos")]
+const PROGRAMS: &[&str] = &["say"];
+#[cfg(not(target_os = "macos"))]
+const PROGRAMS: &[&str] = &["espeak-ng", "espeak"];
+
+pub struct Speaker {
+    program: &'static str,
+    /// Announcements still being spoken
+    children: Vec<Child>,
+}
+
+/// Whether the program can be found in the PATH
+fn is_installed(program: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+impl Speaker {
+    /// Find the text-to-speech program.
+    pub fn new() -> Result<Speaker, String> {
+        match PROGRAMS.iter().find(|program| is_installed(program)) {
+            Some(program) => Ok(Speaker {
+                program,
+                children: vec![],
+            }),
+            None => Err(format!(
+                "Warning: cannot speak, none of these programs was found: {}",
+                PROGRAMS.join(", ")
+            )),
+        }
+    }
+
+    /// Start speaking the text in the background.
+    pub fn speak(&mut self, text: &str) {
+        // Reap the previous announcements once they are over
+        self.children
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        if let Ok(child) = Command::new(self.program)
+            .arg(text)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            self.children.push(child);
+        }
+    }
+}
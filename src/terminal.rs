@@ -0,0 +1,64 @@
+//! Restoration of the terminal once the workout is over.
+
+use std::io::{self, Write};
+
+use crate::Options;
+use termion::raw::RawTerminal;
+use termion::{clear, color, cursor, style};
+
+/// Terminal that can leave the raw mode
+pub trait RawMode: Write {
+    fn suspend_raw_mode(&self) -> io::Result<()>;
+}
+
+impl<W: Write> RawMode for RawTerminal<W> {
+    fn suspend_raw_mode(&self) -> io::Result<()> {
+        RawTerminal::suspend_raw_mode(self)
+    }
+}
+
+/// Bring the terminal back to a usable state when dropped: the screen is cleared, the cursor
+/// shown, the color and the title reset and the raw mode suspended. As it also happens on panic,
+/// the shell is never left in a broken state.
+pub struct TerminalGuard<T: RawMode> {
+    terminal: T,
+    no_color: bool,
+    bold: bool,
+    set_title: bool,
+}
+
+impl<T: RawMode> TerminalGuard<T> {
+    /// Hide the cursor and clear the screen, until the guard is dropped.
+    pub fn new(mut terminal: T, opts: &Options) -> TerminalGuard<T> {
+        let _ = write!(terminal, "{}{}", clear::All, cursor::Hide);
+        let _ = terminal.flush();
+        TerminalGuard {
+            terminal,
+            no_color: opts.no_color,
+            bold: opts.bold,
+            set_title: opts.set_title,
+        }
+    }
+}
+
+impl<T: RawMode> Drop for TerminalGuard<T> {
+    fn drop(&mut self) {
+        // Keep the panic message on the screen
+        if !std::thread::panicking() {
+            let _ = write!(self.terminal, "{}{}", clear::All, cursor::Goto(1, 1));
+        }
+        let _ = write!(self.terminal, "{}", cursor::Show);
+        if !self.no_color {
+            let _ = write!(self.terminal, "{}", color::Fg(color::Reset));
+            // Resetting the color leaves the text bold
+            if self.bold {
+                let _ = write!(self.terminal, "{}", style::Reset);
+            }
+        }
+        if self.set_title {
+            let _ = write!(self.terminal, "\x1b]0;\x07");
+        }
+        let _ = self.terminal.flush();
+        let _ = self.terminal.suspend_raw_mode();
+    }
+}
@@ -0,0 +1,133 @@
+//! Richer layout of the countdowns with `--tui`: the remaining time above a gauge, the time of
+//! the whole workout, then the list of its phases with the current one highlighted, and the keys
+//! that can be pressed on the last line.
+
+use termion::color::{self, Color};
+use termion::style;
+
+use crate::{fg, format_duration, help_line, pin_to_bottom, Controls, Io, Options, Session};
+
+/// Bar of `width` characters filled according to `progress` (between 0 and 1), followed by the
+/// percentage, e.g. "██████░░░░ 60%"
+fn gauge(progress: f64, width: u16) -> String {
+    let percent = format!(" {:>3}%", (progress * 100.0).round() as u32);
+    let inner = (width as usize).saturating_sub(percent.len());
+    let filled = (progress * inner as f64).round() as usize;
+    format!(
+        "{}{}{}",
+        "█".repeat(filled),
+        "░".repeat(inner - filled),
+        percent
+    )
+}
+
+/// Lines listing the phases of the workout, at most `rows` of them around the current one. The
+/// phases done are dimmed, the current one is marked and has the color of the countdown.
+fn plan(opts: &Options, session: &Session, color: &dyn Color, rows: usize) -> Vec<String> {
+    let current = session.current.get();
+    let width = session
+        .plan
+        .iter()
+        .map(|(text, _)| text.chars().count())
+        .max()
+        .unwrap_or(0);
+    // The phase done just before stays in sight
+    let first = current
+        .saturating_sub(1)
+        .min(session.plan.len().saturating_sub(rows));
+    session
+        .plan
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(i, (text, duration))| {
+            let line = format!(
+                "{}{:<width$}  {}",
+                if i == current { "▶ " } else { "  " },
+                text,
+                format_duration(*duration as u64),
+                width = width
+            );
+            match (opts.no_color, i.cmp(&current)) {
+                (true, _) | (false, std::cmp::Ordering::Greater) => line,
+                (false, std::cmp::Ordering::Less) => {
+                    format!("{}{}{}", style::Faint, line, style::Reset)
+                }
+                (false, std::cmp::Ordering::Equal) => {
+                    format!("{}{}{}{}", style::Bold, fg(opts, color), line, style::Reset)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Build the screen of a countdown of the workout, given its `label` and its remaining time
+/// (`seconds`, already styled). The `notes` are displayed below the time of the workout.
+pub(crate) fn message(
+    io: &Io,
+    opts: &Options,
+    (label, seconds): (&str, &str),
+    (progress, color): (f64, &dyn Color),
+    controls: Controls,
+    session: &Session,
+    notes: &[String],
+) -> String {
+    let (width, height) = io.output.size();
+    let (bold, reset) = if opts.no_color {
+        (String::new(), String::new())
+    } else {
+        (style::Bold.to_string(), style::Reset.to_string())
+    };
+    let elapsed = format_duration((io.clock.now() - session.start).as_secs());
+    let remaining = session
+        .total
+        .unwrap_or(0)
+        .saturating_sub(session.active(io).as_secs());
+    let mut lines = vec![
+        format!(
+            "{}{}{}{}",
+            bold,
+            fg(opts, color),
+            label.replace('\n', ", "),
+            reset
+        ),
+        seconds.to_string(),
+        format!("{}{}{}", fg(opts, color), gauge(progress, width), reset),
+        format!(
+            "Elapsed {} · Remaining {}",
+            elapsed,
+            format_duration(remaining)
+        ),
+    ];
+    lines.extend_from_slice(notes);
+    lines.push(String::new());
+    lines.push(format!(
+        "{}{}Schedule{}",
+        bold,
+        fg(opts, &color::Blue),
+        reset
+    ));
+    // The last line is left to the keys
+    let rows = (height as usize).saturating_sub(lines.len() + 1);
+    lines.extend(plan(opts, session, color, rows));
+    let lines: Vec<String> = lines.iter().map(|line| truncate(line, width)).collect();
+    pin_to_bottom(io, &lines.join("\n"), &[help_line(opts, controls, width)])
+}
+
+/// Cut the line to `width` visible characters, keeping its escape sequences
+fn truncate(line: &str, width: u16) -> String {
+    let mut result = String::new();
+    let mut visible = 0;
+    let mut in_escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            c if in_escape => in_escape = !c.is_ascii_alphabetic(),
+            _ if visible == width as usize => continue,
+            _ => visible += 1,
+        }
+        result.push(c);
+    }
+    result
+}
@@ -0,0 +1,267 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Command running reps with the given arguments, without the environment variables giving it
+/// defaults.
+fn command(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_reps"));
+    for (name, _) in std::env::vars() {
+        if name.starts_with("REPS_") {
+            command.env_remove(name);
+        }
+    }
+    command.args(args);
+    command
+}
+
+fn reps(args: &[&str]) -> Output {
+    command(args).output().unwrap()
+}
+
+/// Empty directory of the test, e.g. to hold the configuration of reps
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("reps-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run reps with its configuration and state in `home`
+fn reps_in(home: &Path, args: &[&str]) -> Output {
+    command(args)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_STATE_HOME", home.join("state"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn environment_gives_the_defaults() {
+    let plan = |command: &mut Command| {
+        let output = command.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    // Built-in default
+    assert!(plan(&mut command(&["2", "5", "--dry-run"])).contains("Starting in  blue    0:03\n"));
+    // Environment over the built-in default
+    let with_env = |args| {
+        let mut command = command(args);
+        command.env("REPS_PREP", "10").env("REPS_RELAX_TIME", "7");
+        command
+    };
+    let output = plan(&mut with_env(&["2", "5", "--dry-run"]));
+    assert!(output.contains("Starting in  blue    0:10\n"));
+    assert!(output.contains("Relax!       green   0:07\n"));
+    // Command line over the environment
+    let output = plan(&mut with_env(&["2", "5", "2", "--prep", "0", "--dry-run"]));
+    assert!(!output.contains("Starting in"));
+    assert!(output.contains("Relax!   green   0:02\n"));
+    // An empty variable is not set
+    let output = plan(command(&["2", "5", "--dry-run"]).env("REPS_PREP", ""));
+    assert!(output.contains("Starting in  blue    0:03\n"));
+
+    let output = command(&["2", "5", "--dry-run"])
+        .env("REPS_PREP", "soon")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Invalid REPS_PREP: "));
+}
+
+#[test]
+fn until_stop_takes_the_durations() {
+    let output = reps(&["--until-stop", "30", "10", "--dry-run"]);
+    assert!(output.status.success());
+    let plan = String::from_utf8(output.stdout).unwrap();
+    assert!(plan.contains("Rep 1        red     0:30\n"));
+    assert!(plan.contains("Relax!       green   0:10\n"));
+
+    let output = reps(&["--until-stop", "1m", "--dry-run"]);
+    assert!(output.status.success());
+    let plan = String::from_utf8(output.stdout).unwrap();
+    assert!(plan.contains("Rep 1        red     1:00\n"));
+    assert!(!plan.contains("Relax!"));
+
+    // There is no number of reps to give
+    let output = reps(&["--until-stop", "30", "10", "5", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("cannot be used with '[num_reps]'"));
+}
+
+#[test]
+fn invalid_durations_name_the_argument() {
+    let output = reps(&["3", "1:75", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains(
+        "invalid value '1:75' for '[rep_time]': '1:75' is not a valid duration (expected e.g. 90, \
+         90s, 1m30s or 1:30)"
+    ));
+}
+
+#[test]
+fn durations_of_a_config_are_checked() {
+    let path = std::env::temp_dir().join(format!("reps-config-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        "[[exercise]]\nname = \"Plank\"\nreps = 2\nrep_time = \"25h\"\nrelax_time = 10\n",
+    )
+    .unwrap();
+    let output = reps(&["--config", path.to_str().unwrap(), "--dry-run"]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Plank: \"25h\" is longer than a day"));
+}
+
+#[test]
+fn presets_are_checked() {
+    let home = temp_dir("presets");
+    let presets = home.join("config/reps/presets");
+    std::fs::create_dir_all(&presets).unwrap();
+
+    // Missing fields keep their default, the speed of the clock included
+    std::fs::write(presets.join("short.toml"), "num_reps = 2\nrep_time = 5\n").unwrap();
+    let output = reps_in(&home, &["--preset", "short", "--dry-run"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Rep 2/2  red     0:05\n"));
+
+    std::fs::write(
+        presets.join("inverted.toml"),
+        "num_reps = 2\nrep_time = 5\nrelax_range = [9, 3]\n",
+    )
+    .unwrap();
+    let output = reps_in(&home, &["--preset", "inverted", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains(
+        "Invalid saved workout: relax_range: the minimum of \"9:3\" is above the maximum"
+    ));
+
+    std::fs::write(
+        presets.join("stopped.toml"),
+        "num_reps = 2\nrep_time = 5\nspeed = 0.0\n",
+    )
+    .unwrap();
+    let output = reps_in(&home, &["--preset", "stopped", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Invalid saved workout: speed: \"0\" is not a positive number"));
+
+    std::fs::write(
+        presets.join("frantic.toml"),
+        "num_reps = 2\nrep_time = 5\ncadence = 6000\n",
+    )
+    .unwrap();
+    let output = reps_in(&home, &["--preset", "frantic", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Invalid saved workout: cadence: 6000 is above 300"));
+    std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn cadence_is_capped() {
+    let output = reps(&["2", "5", "--cadence", "300", "--dry-run"]);
+    assert!(output.status.success());
+    let output = reps(&["2", "5", "--cadence", "301", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("301 is not in 1..=300"));
+}
+
+#[test]
+fn rounds_repeat_the_workout() {
+    let output = reps(&[
+        "2",
+        "5",
+        "3",
+        "--rounds",
+        "2",
+        "--set-rest",
+        "10",
+        "--dry-run",
+    ]);
+    assert!(output.status.success());
+    let plan = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(plan.matches("Starting in").count(), 1);
+    assert!(plan.contains("Round 1/2 Rep 1/2  red     0:05\n"));
+    assert!(plan.contains("Round rest         cyan    0:10\n"));
+    assert!(plan.contains("Round 2/2 Rep 2/2  red     0:05\n"));
+
+    let output = reps(&["2", "5", "--rounds", "2", "--sets", "3", "--dry-run"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn replay_the_reps_planned() {
+    let home = temp_dir("replay");
+    let history = home.join("history.csv");
+    std::fs::write(
+        &history,
+        "timestamp,reps,rep_time,relax_time,duration,completed,exercise,planned_reps\n\
+         1704067200,5,30,10,190,true,Rep,5\n\
+         1704153600,2,20,5,50,false,Rep,4\n",
+    )
+    .unwrap();
+    // The last workout, stopped after 2 of its 4 reps
+    let output = reps(&["replay", history.to_str().unwrap(), "--dry-run"]);
+    assert!(output.status.success());
+    let plan = String::from_utf8(output.stdout).unwrap();
+    assert!(plan.contains("Rep 4/4      red     0:20\n"));
+    assert!(plan.contains("Relax!       green   0:05\n"));
+
+    // Written before the reps planned were recorded
+    std::fs::write(
+        &history,
+        "timestamp,reps,rep_time,relax_time,duration,completed\n1704067200,3,30,10,110,false\n",
+    )
+    .unwrap();
+    let output = reps(&["replay", history.to_str().unwrap(), "--dry-run"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Rep 3/3      red     0:30\n"));
+
+    std::fs::write(&history, "when,what\n1704067200,squats\n").unwrap();
+    let output = reps(&["replay", history.to_str().unwrap(), "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("it was written by another version of reps"));
+    std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn one_line_per_phase_when_piped() {
+    let home = temp_dir("piped");
+    let output = reps_in(&home, &["2", "1", "1", "--prep", "1", "--speed", "20"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[..5],
+        [
+            "[0:00] Starting in (0:01)",
+            "[0:00] Rep 1/2 (0:01)",
+            "[0:00] Relax! (0:01)",
+            "[0:00] Rep 2/2 (0:01)",
+            "2 reps completed",
+        ]
+    );
+    // Without the escape sequences drawing the screen
+    assert!(!stdout.contains('\x1b'));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("the phases are printed one per line instead"));
+}
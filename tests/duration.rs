@@ -0,0 +1,36 @@
+use reps::parse_duration;
+
+#[test]
+fn durations() {
+    assert_eq!(parse_duration("90"), Ok(90));
+    assert_eq!(parse_duration("90s"), Ok(90));
+    assert_eq!(parse_duration("2m"), Ok(120));
+    assert_eq!(parse_duration("1m30s"), Ok(90));
+    assert_eq!(parse_duration("1h"), Ok(3600));
+    assert_eq!(parse_duration("1:30"), Ok(90));
+    assert_eq!(parse_duration("1:00:00"), Ok(3600));
+}
+
+#[test]
+fn invalid_durations() {
+    for s in [
+        "",
+        "abc",
+        "1x",
+        "m",
+        "30s1m",
+        "1m1m",
+        "1m30",
+        "1:75",
+        "1:2:3:4",
+        "4294967296",
+        "2000000h",
+        "99999999:00",
+    ] {
+        assert!(parse_duration(s).is_err(), "{:?} was accepted", s);
+    }
+    assert_eq!(
+        parse_duration("1:75"),
+        Err("'1:75' is not a valid duration (expected e.g. 90, 90s, 1m30s or 1:30)".to_string())
+    );
+}
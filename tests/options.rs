@@ -0,0 +1,40 @@
+extern crate reps;
+extern crate toml;
+
+use reps::{Exercise, Mode, Options};
+
+#[test]
+fn options_survive_a_round_trip_through_toml() {
+    let opts = Options {
+        mode: Mode::Emom,
+        num_reps: 5,
+        rep_time: 40,
+        relax_range: Some((10, 20)),
+        exercises: vec![Exercise {
+            name: "Plank".to_string(),
+            reps: 2,
+            rep_time: 30,
+            relax_time: 15,
+        }],
+        speed: 1.0,
+        ..Default::default()
+    };
+    let saved = toml::to_string(&opts).unwrap();
+    let loaded: Options = toml::from_str(&saved).unwrap();
+    assert_eq!(loaded.mode, Mode::Emom);
+    assert_eq!(loaded.num_reps, 5);
+    assert_eq!(loaded.relax_range, Some((10, 20)));
+    assert_eq!(loaded.exercises, opts.exercises);
+    assert_eq!(toml::to_string(&loaded).unwrap(), saved);
+}
+
+#[test]
+fn missing_options_keep_their_default() {
+    let loaded: Options = toml::from_str("num_reps = 3\nbig = true").unwrap();
+    assert_eq!(loaded.num_reps, 3);
+    assert!(loaded.big);
+    assert_eq!(loaded.rep_time, 0);
+    assert!(loaded.exercises.is_empty());
+    // The real time, rather than a clock that does not move
+    assert_eq!(loaded.speed, 1.0);
+}
@@ -0,0 +1,432 @@
+extern crate reps;
+
+use reps::{Exercise, Options, Palette, Phase, Schedule, Theme};
+
+/// Rep of a single set
+fn rep_of(index: u32, duration: u32) -> Phase {
+    Phase::Rep {
+        exercise: "Rep".to_string(),
+        index,
+        total: 2,
+        set: (1, 1),
+        duration,
+    }
+}
+
+fn rep(index: u32, set: u32, duration: u32) -> Phase {
+    Phase::Rep {
+        exercise: "Rep".to_string(),
+        index,
+        total: 2,
+        set: (set, 2),
+        duration,
+    }
+}
+
+#[test]
+fn from_options() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 30,
+        relax_time: 10,
+        prep_time: 5,
+        warmup: 60,
+        cooldown: 120,
+        sets: 2,
+        set_rest: 90,
+        ..Default::default()
+    };
+    let schedule = Schedule::from_options(&opts);
+
+    assert_eq!(
+        schedule.phases,
+        [
+            Phase::Prep(5),
+            Phase::Warmup(60),
+            rep(1, 1, 30),
+            Phase::Relax(10),
+            rep(2, 1, 30),
+            Phase::Relax(10),
+            Phase::SetRest(90),
+            rep(1, 2, 30),
+            Phase::Relax(10),
+            rep(2, 2, 30),
+            Phase::Cooldown(120),
+        ]
+    );
+    assert_eq!(
+        schedule.total_duration(),
+        5 + 60 + 4 * 30 + 3 * 10 + 90 + 120
+    );
+}
+
+#[test]
+fn trailing_rest() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 30,
+        relax_time: 10,
+        ..Default::default()
+    };
+    // The workout ends with the last rep, unless asked otherwise
+    let schedule = Schedule::from_options(&opts);
+    assert_eq!(schedule.phases.last(), Some(&rep_of(2, 30)));
+    assert_eq!(schedule.total_duration(), 70);
+
+    let schedule = Schedule::from_options(&Options {
+        trailing_rest: true,
+        ..opts
+    });
+    assert_eq!(schedule.phases.last(), Some(&Phase::Relax(10)));
+    assert_eq!(schedule.total_duration(), 80);
+}
+
+#[test]
+fn durations_of_each_rep() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 30,
+        rep_times: vec![20, 40],
+        sets: 2,
+        ..Default::default()
+    };
+    let schedule = Schedule::from_options(&opts);
+
+    let durations: Vec<u32> = schedule.phases.iter().map(Phase::duration).collect();
+    assert_eq!(durations, [20, 40, 0, 20, 40]);
+}
+
+#[test]
+fn back_to_back_reps() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 20,
+        relax_time: 0,
+        trailing_rest: true,
+        ..Default::default()
+    };
+    let schedule = Schedule::from_options(&opts);
+
+    // Not even the trailing rest
+    assert_eq!(schedule.phases, [rep_of(1, 20), rep_of(2, 20)]);
+}
+
+#[test]
+fn relax_after_each_rep() {
+    let opts = Options {
+        num_reps: 3,
+        rep_time: 20,
+        rep_times: vec![20, 30, 25],
+        relax_time: 5,
+        relax_times: vec![10, 15, 5],
+        trailing_rest: true,
+        ..Default::default()
+    };
+    let schedule = Schedule::from_options(&opts);
+
+    let durations: Vec<u32> = schedule.phases.iter().map(Phase::duration).collect();
+    assert_eq!(durations, [20, 10, 30, 15, 25, 5]);
+}
+
+#[test]
+fn empty_schedule() {
+    assert_eq!(Schedule::default().total_duration(), 0);
+}
+
+#[test]
+fn dry_run() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 30,
+        relax_time: 10,
+        prep_time: 5,
+        cooldown: 60,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        reps::dry_run(&opts),
+        concat!(
+            "Starting in  blue    0:05\n",
+            "Rep 1/2      red     0:30\n",
+            "Relax!       green   0:10\n",
+            "Rep 2/2      red     0:30\n",
+            "Cooldown     green   1:00\n",
+            "Total: 2:15\n",
+        )
+    );
+}
+
+#[test]
+fn dry_run_with_a_theme() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 30,
+        relax_time: 10,
+        prep_time: 5,
+        theme: Theme {
+            rep: Some((255, 136, 0)),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // The phases missing from the theme keep the color of the palette
+    assert_eq!(
+        reps::dry_run(&opts),
+        concat!(
+            "Starting in  blue    0:05\n",
+            "Rep 1/2      #ff8800 0:30\n",
+            "Relax!       green   0:10\n",
+            "Rep 2/2      #ff8800 0:30\n",
+            "Total: 1:15\n",
+        )
+    );
+}
+
+#[test]
+fn dry_run_with_the_bright_palette() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 30,
+        relax_time: 10,
+        prep_time: 5,
+        palette: Palette::Bright,
+        ..Default::default()
+    };
+
+    // The durations stay aligned after the longer names
+    assert_eq!(
+        reps::dry_run(&opts),
+        concat!(
+            "Starting in  light blue  0:05\n",
+            "Rep 1/2      light red   0:30\n",
+            "Relax!       light green 0:10\n",
+            "Rep 2/2      light red   0:30\n",
+            "Total: 1:15\n",
+        )
+    );
+}
+
+#[test]
+fn emom_schedule() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 60,
+        prep_time: 5,
+        ..Default::default()
+    };
+    let schedule = Schedule::emom(&opts);
+
+    assert_eq!(schedule.phases.len(), 3);
+    assert_eq!(schedule.total_duration(), 125);
+}
+
+#[test]
+fn until_stop_schedule() {
+    let opts = Options {
+        num_reps: 0,
+        rep_time: 30,
+        relax_time: 10,
+        prep_time: 5,
+        cooldown: 60,
+        ..Default::default()
+    };
+    assert!(opts.until_stop());
+    assert_eq!(Schedule::from_options(&opts).phases, [Phase::Prep(5)]);
+    assert_eq!(
+        reps::dry_run(&opts),
+        concat!(
+            "Starting in  blue    0:05\n",
+            "Rep 1        red     0:30\n",
+            "Relax!       green   0:10\n",
+            "Then again, until stopped\n",
+        )
+    );
+}
+
+#[test]
+fn long_breaks() {
+    let opts = Options {
+        num_reps: 3,
+        rep_time: 25,
+        relax_time: 5,
+        long_break: 15,
+        long_break_every: 2,
+        ..Default::default()
+    };
+    let relax: Vec<u32> = Schedule::from_options(&opts)
+        .phases
+        .iter()
+        .filter_map(|phase| match phase {
+            Phase::Relax(duration) => Some(*duration),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(relax, [5, 15]);
+}
+
+#[test]
+fn encode_and_decode() {
+    let exercise = |name: &str, reps| Exercise {
+        name: name.to_string(),
+        reps,
+        rep_time: 30,
+        relax_time: 10,
+    };
+    let opts = Options {
+        exercises: vec![exercise("Push-ups", 2), exercise("Plank, side", 1)],
+        prep_time: 5,
+        cooldown: 60,
+        sets: 2,
+        set_rest: 90,
+        ..Default::default()
+    };
+    let schedule = Schedule::from_options(&opts);
+
+    let code = schedule.encode();
+    assert_eq!(
+        code,
+        concat!(
+            "1,P5,Push-ups:30,R10,30,R10,Plank%2C%20side:30,R10,",
+            "S90,Push-ups:30,R10,30,R10,Plank%2C%20side:30,C60"
+        )
+    );
+    assert_eq!(Schedule::decode(&code), Ok(schedule));
+
+    let emom = Schedule::emom(&Options {
+        num_reps: 3,
+        rep_time: 60,
+        ..Default::default()
+    });
+    assert_eq!(Schedule::decode(&emom.encode()), Ok(emom));
+}
+
+#[test]
+fn decode_errors() {
+    let error = |code| Schedule::decode(code).unwrap_err();
+    assert_eq!(
+        error("2,P5"),
+        "Invalid workout \"2,P5\": it was not exported by this version of reps"
+    );
+    assert_eq!(error("1"), "Invalid workout \"1\": there is nothing to do");
+    assert_eq!(
+        error("1,P5,30"),
+        "Invalid workout \"1,P5,30\": \"30\" has no exercise"
+    );
+    assert_eq!(
+        error("1,X5"),
+        "Invalid workout \"1,X5\": \"X5\" is not a phase"
+    );
+    assert_eq!(
+        error("1,Rep%2:30"),
+        "Invalid workout \"1,Rep%2:30\": \"Rep%2\" is not a valid name"
+    );
+    assert_eq!(
+        error("1,Rep:0"),
+        "Invalid workout \"1,Rep:0\": \"0\" is not a duration of work, it must be at least 1s"
+    );
+    assert_eq!(
+        error("1,Rep:30,0"),
+        "Invalid workout \"1,Rep:30,0\": \"0\" is not a duration of work, it must be at least 1s"
+    );
+    assert_eq!(
+        error("1,Rep:30,R86401"),
+        "Invalid workout \"1,Rep:30,R86401\": \"86401\" is longer than a day"
+    );
+}
+
+#[test]
+fn plan() {
+    let plan = "# Sprints\nprep:5\nrep:30\n\n  rest: 1m\nrep:0:45\n";
+    assert_eq!(
+        Schedule::from_plan(plan),
+        Ok(Schedule {
+            phases: vec![
+                Phase::Prep(5),
+                rep_of(1, 30),
+                Phase::Relax(60),
+                rep_of(2, 45)
+            ]
+        })
+    );
+
+    let error = |plan| Schedule::from_plan(plan).unwrap_err();
+    assert_eq!(
+        error("# Nothing\n\n"),
+        "Invalid plan: there is nothing to do"
+    );
+    assert_eq!(
+        error("rep:30\nrep 30"),
+        "Invalid plan, line 2: \"rep 30\" is not a phase, e.g. \"rep:30\""
+    );
+    assert_eq!(
+        error("sprint:10"),
+        "Invalid plan, line 1: \"sprint\" is not a kind of phase: prep, warmup, rep, rest or \
+         cooldown"
+    );
+    assert_eq!(
+        error("rep:0"),
+        "Invalid plan, line 1: \"0\" is not a duration of work, it must be at least 1s"
+    );
+    assert_eq!(
+        error("rep:30\nrest:25h"),
+        "Invalid plan, line 2: \"25h\" is longer than a day"
+    );
+}
+
+#[test]
+fn shuffle() {
+    let exercise = |name: &str| Exercise {
+        name: name.to_string(),
+        reps: 1,
+        rep_time: 30,
+        relax_time: 10,
+    };
+    let opts = Options {
+        exercises: ["Squats", "Plank", "Lunges", "Burpees", "Crunches"]
+            .iter()
+            .map(|name| exercise(name))
+            .collect(),
+        warmup: 60,
+        cooldown: 120,
+        sets: 2,
+        shuffle: true,
+        seed: Some(7),
+        ..Default::default()
+    };
+    let names = |schedule: &Schedule| -> Vec<String> {
+        schedule
+            .phases
+            .iter()
+            .filter_map(|phase| match phase {
+                Phase::Rep { exercise, .. } => Some(exercise.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    let schedule = Schedule::from_options(&opts);
+
+    // The same order with the same seed, and in both sets
+    assert_eq!(schedule, Schedule::from_options(&opts));
+    let order = names(&schedule);
+    assert_eq!(order[..5], order[5..]);
+    let mut sorted = order[..5].to_vec();
+    sorted.sort();
+    assert_eq!(sorted, ["Burpees", "Crunches", "Lunges", "Plank", "Squats"]);
+    assert_eq!(schedule.phases.first(), Some(&Phase::Warmup(60)));
+    assert_eq!(schedule.phases.last(), Some(&Phase::Cooldown(120)));
+
+    // Another seed gives another order, among the 120 possible ones
+    let orders: Vec<Vec<String>> = (0..10)
+        .map(|seed| {
+            names(&Schedule::from_options(&Options {
+                exercises: opts.exercises.clone(),
+                shuffle: true,
+                seed: Some(seed),
+                ..Default::default()
+            }))
+        })
+        .collect();
+    assert!(orders.iter().any(|other| other != &orders[0]));
+}
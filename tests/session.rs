@@ -0,0 +1,2219 @@
+extern crate reps;
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use reps::{
+    start_emom, start_reps, start_stopwatch, wait_to_start, Clock, CueIntensity, Event, Exercise,
+    Input, Io, Keys, Mode, Options, Output, Palette, RepsError, Stats, DEFAULT_SIZE,
+    SUMMARY_DURATION,
+};
+
+/// Clock that advances instantly when sleeping.
+struct FakeClock {
+    start: Instant,
+    elapsed: Cell<Duration>,
+}
+
+impl FakeClock {
+    fn new() -> Rc<FakeClock> {
+        Rc::new(FakeClock {
+            start: Instant::now(),
+            elapsed: Cell::new(Duration::from_secs(0)),
+        })
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration)
+    }
+
+    fn sleep_until(&self, deadline: Instant) {
+        self.elapsed
+            .set(self.elapsed.get().max(deadline - self.start))
+    }
+}
+
+/// Input that returns each key once the clock reached the associated time.
+struct FakeInput {
+    clock: Rc<FakeClock>,
+    keys: Vec<(Duration, u8)>,
+}
+
+impl Input for FakeInput {
+    fn next_key(&mut self) -> Option<std::io::Result<u8>> {
+        match self.keys.first() {
+            Some(&(at, key)) if at <= self.clock.elapsed.get() => {
+                self.keys.remove(0);
+                Some(Ok(key))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Output that records the rendered messages, without the escape sequences, as well as the spoken
+/// text.
+struct Recorder {
+    frames: Vec<String>,
+    width: u16,
+}
+
+impl Output for Recorder {
+    fn render(&mut self, message: &str) {
+        let mut frame = String::new();
+        let mut in_escape = false;
+        for c in message.chars() {
+            match c {
+                '\x1b' => in_escape = true,
+                c if in_escape => in_escape = !c.is_ascii_alphabetic(),
+                c => frame.push(c),
+            }
+        }
+        self.frames.push(frame);
+    }
+
+    fn size(&self) -> (u16, u16) {
+        (self.width, 24)
+    }
+
+    fn speak(&mut self, text: &str) {
+        self.frames.push(format!("Says {}", text));
+    }
+}
+
+/// Remove the progress bars, the status of the session and the dots of the reps from each frame,
+/// to keep only the labels and the remaining time.
+fn headers(frames: &[String]) -> Vec<String> {
+    frames
+        .iter()
+        .map(|frame| {
+            frame
+                .lines()
+                .filter(|line| !line.is_empty() && !line.starts_with(['█', '░', '[', '━', '─']))
+                .filter(|line| !line.starts_with("Elapsed"))
+                .filter(|line| !line.chars().all(|c| c == '●' || c == '○'))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect()
+}
+
+fn run_with_width(
+    opts: &Options,
+    keys: Vec<(Duration, u8)>,
+    width: u16,
+) -> (Result<(), RepsError>, Vec<String>, Duration) {
+    run_with_stats(opts, keys, width, &mut Stats::default())
+}
+
+fn run_with_stats(
+    opts: &Options,
+    keys: Vec<(Duration, u8)>,
+    width: u16,
+    stats: &mut Stats,
+) -> (Result<(), RepsError>, Vec<String>, Duration) {
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys,
+    };
+    let mut output = Recorder {
+        frames: vec![],
+        width,
+    };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        opts,
+        stats,
+    );
+    (result, output.frames, clock.elapsed.get())
+}
+
+fn run(
+    opts: &Options,
+    keys: Vec<(Duration, u8)>,
+) -> (Result<(), RepsError>, Vec<String>, Duration) {
+    run_with_width(opts, keys, 80)
+}
+
+fn options() -> Options {
+    Options {
+        num_reps: 2,
+        rep_time: 2,
+        relax_time: 1,
+        prep_time: 3,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn full_session() {
+    let (result, frames, elapsed) = run(&options(), vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        vec![
+            "Starting in\n3s",
+            "Starting in\n2s",
+            "Starting in\n1s",
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Relax!\n1s",
+            "Rep 2/2\n2s",
+            "Rep 2/2\n1s",
+        ]
+    );
+    assert_eq!(elapsed, Duration::from_secs(8));
+}
+
+#[test]
+fn five_reps_without_waiting() {
+    let opts = Options {
+        num_reps: 5,
+        rep_time: 2,
+        relax_time: 1,
+        prep_time: 0,
+        ..Default::default()
+    };
+    let started = Instant::now();
+    let (result, frames, elapsed) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    let mut expected: Vec<String> = (1..=5)
+        .flat_map(|rep| {
+            [
+                format!("Rep {}/5\n2s", rep),
+                format!("Rep {}/5\n1s", rep),
+                "Relax!\n1s".to_string(),
+            ]
+        })
+        .collect();
+    // Over with the last rep
+    expected.pop();
+    assert_eq!(headers(&frames), expected);
+    // Exactly the planned duration, without any drift
+    assert_eq!(elapsed, Duration::from_secs(14));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn pause_and_resume() {
+    let keys = vec![
+        (Duration::from_millis(3500), b' '),
+        (Duration::from_secs(10), b' '),
+    ];
+    let (result, frames, elapsed) = run(&options(), keys);
+
+    assert_eq!(result, Ok(()));
+    // Paused for 6.5s, the screen is updated every second
+    assert_eq!(
+        headers(&frames[3..12]),
+        [
+            "Rep 1/2\n2s",
+            "PAUSED 0:00\nBack to Rep 1/2, 2s left",
+            "PAUSED 0:01\nBack to Rep 1/2, 2s left",
+            "PAUSED 0:02\nBack to Rep 1/2, 2s left",
+            "PAUSED 0:03\nBack to Rep 1/2, 2s left",
+            "PAUSED 0:04\nBack to Rep 1/2, 2s left",
+            "PAUSED 0:05\nBack to Rep 1/2, 2s left",
+            "PAUSED 0:06\nBack to Rep 1/2, 2s left",
+            "Rep 1/2\n2s"
+        ]
+    );
+    assert_eq!(frames.len(), 16);
+    assert!(elapsed > Duration::from_secs(14));
+}
+
+#[test]
+fn escape_stops_the_session() {
+    let (result, frames, elapsed) = run(&options(), vec![(Duration::from_millis(4200), 27)]);
+
+    assert_eq!(result, Err(RepsError::UserExit));
+    assert_eq!(headers(&frames).last().unwrap(), "Rep 1/2\n1s");
+    assert!(elapsed < Duration::from_millis(4300));
+}
+
+#[test]
+fn progress_bar() {
+    let (_, frames, _) = run(&options(), vec![]);
+    assert_eq!(
+        frames[3].lines().nth(2).unwrap(),
+        "░".repeat(80),
+        "The bar should be empty at the start of the rep"
+    );
+    assert_eq!(
+        frames[4].lines().nth(2).unwrap(),
+        format!("{}{}", "█".repeat(40), "░".repeat(40))
+    );
+
+    // Below "Starting in", which takes two lines
+    let (_, frames, _) = run_with_width(&options(), vec![], 10);
+    assert_eq!(frames[1].lines().nth(3).unwrap(), "[###-----]");
+}
+
+#[test]
+fn emom_counts_completed_minutes() {
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![(Duration::from_secs(150), 27)],
+    };
+    let mut output = Recorder {
+        frames: vec![],
+        width: 80,
+    };
+    let opts = Options {
+        mode: Mode::Emom,
+        num_reps: 5,
+        rep_time: 60,
+        ..Default::default()
+    };
+    let mut completed = 0;
+    let result = start_emom(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &opts,
+        &mut completed,
+    );
+
+    assert!(result.is_err());
+    assert_eq!(completed, 2);
+    assert_eq!(headers(&output.frames)[60], "Minute 2/5\n60s");
+}
+
+#[test]
+fn several_exercises() {
+    let exercise = |name: &str, reps| Exercise {
+        name: name.to_string(),
+        reps,
+        rep_time: 1,
+        relax_time: 1,
+    };
+    let opts = Options {
+        exercises: vec![exercise("Push-ups", 2), exercise("Plank", 1)],
+        ..Default::default()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        vec![
+            "Push-ups 1/2\n1s",
+            "Relax!\n1s",
+            "Push-ups 2/2\n1s",
+            "Relax!\n1s",
+            "Plank 1/1\n1s",
+        ]
+    );
+}
+
+#[test]
+fn rep_names() {
+    let opts = Options {
+        names: vec!["Push-ups".to_string()],
+        ..options()
+    };
+    let (_, frames, _) = run(&opts, vec![]);
+    assert_eq!(headers(&frames)[3], "Push-ups\n2s");
+    assert_eq!(headers(&frames)[6], "Rep 2/2\n2s");
+}
+
+#[test]
+fn sets() {
+    let opts = Options {
+        num_reps: 1,
+        rep_time: 1,
+        relax_time: 1,
+        sets: 2,
+        set_rest: 2,
+        ..Default::default()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        vec![
+            "Set 1/2\nRep 1/1\n1s",
+            "Relax!\n1s",
+            "Set rest\n2s",
+            "Set rest\n1s",
+            "Set 2/2\nRep 1/1\n1s",
+        ]
+    );
+}
+
+#[test]
+fn prepare_once_for_all_the_sets() {
+    let opts = Options {
+        num_reps: 1,
+        rep_time: 1,
+        relax_time: 1,
+        prep_time: 1,
+        sets: 2,
+        set_rest: 1,
+        ..Default::default()
+    };
+    // Paused during the second set
+    let keys = vec![
+        (Duration::from_millis(4500), b' '),
+        (Duration::from_secs(6), b' '),
+    ];
+    let (result, frames, elapsed) = run(&opts, keys);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        vec![
+            "Starting in\n1s",
+            "Set 1/2\nRep 1/1\n1s",
+            "Relax!\n1s",
+            "Set rest\n1s",
+            "Set 2/2\nRep 1/1\n1s",
+            "PAUSED 0:00\nBack to Set 2/2, Rep 1/1, 1s left",
+            "PAUSED 0:01\nBack to Set 2/2, Rep 1/1, 1s left",
+            "Set 2/2\nRep 1/1\n1s",
+        ]
+    );
+    assert!(elapsed >= Duration::from_millis(6500));
+}
+
+#[test]
+fn rounds() {
+    let opts = Options {
+        num_reps: 1,
+        rep_time: 1,
+        relax_time: 1,
+        prep_time: 1,
+        sets: 2,
+        set_rest: 1,
+        set_label: Some("Round".to_string()),
+        ..Default::default()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        vec![
+            "Starting in\n1s",
+            "Round 1/2\nRep 1/1\n1s",
+            "Relax!\n1s",
+            "Round rest\n1s",
+            "Round 2/2\nRep 1/1\n1s",
+        ]
+    );
+}
+
+#[test]
+fn skip_to_the_next_phase() {
+    let keys = vec![
+        (Duration::from_millis(3500), b'n'),
+        (Duration::from_millis(3500), 27),
+    ];
+    let (result, frames, _) = run(&options(), keys.clone());
+    assert_eq!(result, Err(RepsError::UserExit), "ESC has the priority");
+    assert_eq!(headers(&frames).last().unwrap(), "Rep 1/2\n2s");
+
+    let (result, frames, elapsed) = run(&options(), keys[..1].to_vec());
+    assert_eq!(result, Ok(()));
+    assert_eq!(headers(&frames[3..5]), ["Rep 1/2\n2s", "Relax!\n1s"]);
+    assert_eq!(elapsed, Duration::from_millis(6500));
+}
+
+#[test]
+fn adjust_the_remaining_time() {
+    let opts = Options {
+        num_reps: 1,
+        rep_time: 5,
+        relax_time: 20,
+        trailing_rest: true,
+        ..Default::default()
+    };
+    let keys = vec![
+        (Duration::from_millis(1500), b'+'),
+        (Duration::from_millis(16500), b'-'),
+        (Duration::from_millis(16500), b'-'),
+        (Duration::from_millis(16500), b'-'),
+    ];
+    let (result, frames, elapsed) = run(&opts, keys);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames[..4]),
+        [
+            "Rep 1/1\n5s",
+            "Rep 1/1\n4s",
+            "Rep 1/1\n14s\n+10s",
+            "Rep 1/1\n13s"
+        ]
+    );
+    assert_eq!(
+        headers(&frames[15..]),
+        ["Rep 1/1\n1s", "Relax!\n20s", "Relax!\n19s"]
+    );
+    assert_eq!(
+        elapsed,
+        Duration::from_millis(16500),
+        "The relax countdown ends once there is no time left"
+    );
+}
+
+#[test]
+fn restart_the_rep() {
+    let keys = vec![
+        (Duration::from_millis(4500), b'r'),
+        (Duration::from_millis(10000), b'r'),
+    ];
+    let (result, frames, elapsed) = run(&options(), keys);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames[3..]),
+        [
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Relax!\n1s",
+            "Rep 2/2\n2s",
+            "Rep 2/2\n1s",
+        ],
+        "Restarting is only possible during a rep"
+    );
+    assert_eq!(elapsed, Duration::from_millis(9500));
+}
+
+#[test]
+fn only_space_pauses() {
+    let keys = vec![
+        (Duration::from_millis(500), b'x'),
+        (Duration::from_millis(3500), b' '),
+        (Duration::from_secs(5), b'x'),
+        (Duration::from_secs(6), b' '),
+    ];
+    let (result, frames, elapsed) = run(&options(), keys);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames[..5]),
+        [
+            "Starting in\n3s",
+            "Starting in\n2s",
+            "Starting in\n1s",
+            "Rep 1/2\n2s",
+            "PAUSED 0:00\nBack to Rep 1/2, 2s left"
+        ]
+    );
+    assert_eq!(headers(&frames[7..8]), ["Rep 1/2\n2s"]);
+    assert_eq!(elapsed, Duration::from_millis(10500));
+}
+
+#[test]
+fn confirm_exit() {
+    let opts = Options {
+        confirm_exit: true,
+        ..options()
+    };
+    let keys = vec![
+        (Duration::from_millis(3500), 27),
+        (Duration::from_secs(10), b'n'),
+        (Duration::from_secs(11), 3),
+        (Duration::from_secs(12), b'y'),
+    ];
+    let (result, frames, elapsed) = run(&opts, keys);
+
+    assert_eq!(result, Err(RepsError::UserExit));
+    assert_eq!(
+        headers(&frames[3..]),
+        [
+            "Rep 1/2\n2s",
+            "Quit? (y/n)",
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Quit? (y/n)"
+        ]
+    );
+    assert!(elapsed >= Duration::from_secs(12));
+}
+
+#[test]
+fn warmup_and_cooldown() {
+    let opts = Options {
+        num_reps: 1,
+        rep_time: 1,
+        relax_time: 1,
+        prep_time: 1,
+        warmup: 1,
+        cooldown: 1,
+        ..Default::default()
+    };
+    let (_, frames, _) = run(&opts, vec![]);
+    assert_eq!(
+        headers(&frames),
+        [
+            "Starting in\n1s",
+            "Warmup\n1s",
+            "Rep 1/1\n1s",
+            "Cooldown\n1s"
+        ]
+    );
+}
+
+#[test]
+fn elapsed_and_total_time() {
+    let (_, frames, _) = run(&options(), vec![]);
+    // 3s of preparation, then 2 reps of 2s with 1s of relax in between
+    assert!(frames[0].contains("Elapsed 0:00 / Total 0:08"));
+    assert!(frames.last().unwrap().contains("Elapsed 0:07 / Total 0:08"));
+    assert_eq!(reps::format_duration(3725), "1:02:05");
+}
+
+#[test]
+fn stats() {
+    let mut stats = Stats::default();
+    let keys = vec![
+        (Duration::from_millis(3500), b' '),
+        (Duration::from_secs(10), b' '),
+    ];
+    let (result, _, elapsed) = run_with_stats(&options(), keys, 80, &mut stats);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stats.reps, 2);
+    assert_eq!(stats.work_time.as_secs(), 4);
+    assert_eq!(stats.rest_time.as_secs(), 1);
+    assert_eq!(stats.duration, elapsed);
+    assert_eq!(stats.pauses, 1);
+}
+
+#[test]
+fn time_cap() {
+    let capped = |cap_excludes_pauses| Options {
+        max_duration: Some(4),
+        cap_excludes_pauses,
+        ..options()
+    };
+    let mut stats = Stats::default();
+    let (result, frames, elapsed) = run_with_stats(&capped(false), vec![], 80, &mut stats);
+    assert_eq!(result, Err(RepsError::TimeCap));
+    assert_eq!(headers(&frames).last().unwrap(), "Rep 1/2\n1s");
+    assert_eq!(elapsed, Duration::from_secs(4));
+    assert_eq!(stats.work_time.as_secs(), 1);
+
+    // Reached during the pause, or later when it does not count
+    let keys = vec![
+        (Duration::from_millis(3500), b' '),
+        (Duration::from_secs(10), b' '),
+    ];
+    let (result, _, elapsed) = run(&capped(false), keys.clone());
+    assert_eq!(result, Err(RepsError::TimeCap));
+    assert_eq!(elapsed, Duration::from_secs(4));
+    let (result, _, elapsed) = run(&capped(true), keys);
+    assert_eq!(result, Err(RepsError::TimeCap));
+    assert_eq!(elapsed, Duration::from_millis(10500));
+
+    // Never reached by a shorter workout
+    let (result, _, _) = run(
+        &Options {
+            max_duration: Some(60),
+            ..options()
+        },
+        vec![],
+    );
+    assert_eq!(result, Ok(()));
+}
+
+#[test]
+fn partial_stats() {
+    let mut stats = Stats::default();
+    let keys = vec![(Duration::from_millis(6500), 27)];
+    let (result, _, _) = run_with_stats(&options(), keys, 80, &mut stats);
+
+    assert_eq!(result, Err(RepsError::UserExit));
+    assert_eq!(stats.reps, 1);
+    assert_eq!(stats.work_time.as_secs(), 2);
+    assert_eq!(stats.pauses, 0);
+}
+
+#[test]
+fn stopwatch() {
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![
+            (Duration::from_millis(1500), b' '),
+            (Duration::from_secs(5), b' '),
+            (Duration::from_millis(63500), 27),
+        ],
+    };
+    let mut output = Recorder {
+        frames: vec![],
+        width: 80,
+    };
+    let mut elapsed = Duration::from_secs(0);
+    let result = start_stopwatch(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &Options::default(),
+        &mut elapsed,
+    );
+
+    assert_eq!(result, Err(RepsError::UserExit));
+    assert_eq!(
+        &output.frames[..3],
+        [
+            "Stopwatch\n0:00",
+            "Stopwatch\n0:01",
+            "PAUSED 0:00\nBack to the stopwatch at 0:01",
+        ]
+    );
+    assert_eq!(output.frames[6], "Stopwatch\n0:01");
+    assert_eq!(output.frames.last().unwrap(), "Stopwatch\n0:59");
+    assert_eq!(elapsed, Duration::from_secs(59));
+}
+
+#[test]
+fn cadence() {
+    let opts = Options {
+        cadence: 60,
+        ..options()
+    };
+    let (result, frames, elapsed) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    // The screen flashes once per second during the reps, and is restored right after
+    assert_eq!(
+        headers(&frames[3..8]),
+        [
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Rep 1/2\n1s",
+            "Rep 1/2\n1s",
+            "Relax!\n1s"
+        ]
+    );
+    assert_eq!(frames.len(), 12);
+    assert_eq!(elapsed, Duration::from_secs(8));
+}
+
+#[test]
+fn speak() {
+    let opts = Options {
+        speak: true,
+        sets: 2,
+        set_rest: 0,
+        num_reps: 1,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    let spoken: Vec<&String> = frames.iter().filter(|f| f.starts_with("Says")).collect();
+    assert_eq!(
+        spoken,
+        [
+            "Says Starting in",
+            "Says 2",
+            "Says 1",
+            "Says Set 1 of 2, Rep 1 of 1",
+            "Says 1",
+            "Says Relax!",
+            "Says Set 2 of 2, Rep 1 of 1",
+            "Says 1",
+        ]
+    );
+}
+
+#[test]
+fn halfway() {
+    let opts = Options {
+        halfway: true,
+        num_reps: 1,
+        rep_time: 5,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    let notes: Vec<&String> = frames.iter().filter(|f| f.contains("Halfway!")).collect();
+    assert_eq!(notes.len(), 1);
+    assert!(notes[0].starts_with("Rep 1/1\n2s"));
+}
+
+#[test]
+fn center() {
+    let opts = Options {
+        center: true,
+        num_reps: 1,
+        ..options()
+    };
+    let keys = vec![
+        (Duration::from_millis(500), b' '),
+        (Duration::from_secs(1), b' '),
+    ];
+    let (result, frames, _) = run_with_width(&opts, keys, 20);
+
+    assert_eq!(result, Ok(()));
+    let lines: Vec<&str> = frames[0].lines().collect();
+    // 5 lines centered on 24 rows, and the progress of the session on the last one
+    assert_eq!(lines.len(), 24);
+    assert_eq!(lines[9], "    Starting in");
+    assert_eq!(lines[10], "         3s");
+    assert_eq!(lines[11].chars().count(), 20);
+    assert_eq!(lines[13], "         ○");
+    // Wrapped to fit the narrow screen
+    let lines: Vec<&str> = frames[1].lines().collect();
+    assert_eq!(
+        lines[lines.len() - 3..],
+        ["    PAUSED 0:00", "Back to Starting in,", "      3s left"]
+    );
+}
+
+/// Output of a terminal whose size cannot be determined.
+struct UnknownSize {
+    frames: Vec<String>,
+}
+
+impl Output for UnknownSize {
+    fn render(&mut self, message: &str) {
+        self.frames.push(message.to_string());
+    }
+
+    fn size(&self) -> (u16, u16) {
+        reps::screen_size(Err(std::io::Error::other("not a terminal"))).0
+    }
+}
+
+#[test]
+fn unknown_terminal_size() {
+    assert_eq!(reps::screen_size(Ok((120, 40))), ((120, 40), None));
+    let (size, warning) = reps::screen_size(Ok((0, 0)));
+    assert_eq!(size, DEFAULT_SIZE);
+    assert_eq!(
+        warning.as_deref(),
+        Some(
+            "Warning: the size of the terminal is unknown (it has 0 columns and 0 rows), the \
+             screen was drawn for 80x24"
+        )
+    );
+
+    // Still centered, with the bars at the bottom
+    let opts = Options {
+        center: true,
+        big: true,
+        no_color: true,
+        ..options()
+    };
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![],
+    };
+    let mut output = UnknownSize { frames: vec![] };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &opts,
+        &mut Stats::default(),
+    );
+
+    assert_eq!(result, Ok(()));
+    assert!(output
+        .frames
+        .iter()
+        .all(|frame| frame.lines().count() == 24));
+    assert!(output
+        .frames
+        .iter()
+        .all(|frame| frame.lines().all(|line| line.chars().count() <= 80)));
+}
+
+/// Output that becomes narrower once the clock reaches `at`.
+struct Shrinking {
+    recorder: Recorder,
+    clock: Rc<FakeClock>,
+    at: Duration,
+    resized: bool,
+}
+
+impl Output for Shrinking {
+    fn render(&mut self, message: &str) {
+        self.recorder.render(message)
+    }
+
+    fn size(&self) -> (u16, u16) {
+        if self.resized {
+            (40, 24)
+        } else {
+            self.recorder.size()
+        }
+    }
+
+    fn resized(&mut self) -> bool {
+        if !self.resized && self.clock.elapsed.get() >= self.at {
+            self.resized = true;
+            return true;
+        }
+        false
+    }
+}
+
+#[test]
+fn redraw_when_resized() {
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![],
+    };
+    let mut output = Shrinking {
+        recorder: Recorder {
+            frames: vec![],
+            width: 80,
+        },
+        clock: clock.clone(),
+        at: Duration::from_millis(500),
+        resized: false,
+    };
+    let opts = Options {
+        center: true,
+        ..options()
+    };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &opts,
+        &mut Stats::default(),
+    );
+
+    assert_eq!(result, Ok(()));
+    let frames = &output.recorder.frames;
+    // Drawn again right away, still 3 seconds before the first rep
+    assert_eq!(frames[1].lines().nth(10).unwrap().trim(), "3s");
+    assert_eq!(
+        frames[0].lines().nth(9),
+        Some("                                  Starting in")
+    );
+    assert_eq!(frames[1].lines().nth(9), Some("              Starting in"));
+    assert_eq!(frames[2].lines().nth(9), Some("              Starting in"));
+}
+
+#[test]
+fn session_progress_bar() {
+    let opts = Options {
+        num_reps: 1,
+        ..options()
+    };
+    // Paused for 5 seconds during the second of the 5 seconds of the workout
+    let keys = vec![
+        (Duration::from_millis(1500), b' '),
+        (Duration::from_millis(6500), b' '),
+    ];
+    let (result, frames, _) = run_with_width(&opts, keys, 30);
+
+    assert_eq!(result, Ok(()));
+    let bars: Vec<&str> = frames
+        .iter()
+        .filter_map(|frame| frame.lines().last())
+        .filter(|line| line.starts_with(['━', '─']))
+        .collect();
+    let filled: Vec<usize> = bars
+        .iter()
+        .map(|bar| bar.chars().filter(|&c| c == '━').count())
+        .collect();
+    // Drawn again after the pause as it was at 1.5s, the 5 seconds of pause are not counted
+    assert_eq!(filled, [0, 6, 9, 12, 18, 24]);
+    assert!(bars.iter().all(|bar| bar.chars().count() == 30));
+}
+
+#[test]
+fn random_relax() {
+    let opts = Options {
+        num_reps: 20,
+        rep_time: 1,
+        relax_range: Some((2, 4)),
+        prep_time: 0,
+        ..Default::default()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    // The first second of each relax displays its whole duration
+    let relax: Vec<String> = headers(&frames)
+        .windows(2)
+        .filter(|frames| frames[0].starts_with("Rep") && frames[1].starts_with("Relax!"))
+        .map(|frames| frames[1].clone())
+        .collect();
+    // No relax after the last rep
+    assert_eq!(relax.len(), 19);
+    assert!(relax
+        .iter()
+        .all(|relax| ["Relax!\n2s", "Relax!\n3s", "Relax!\n4s"].contains(&relax.as_str())));
+}
+
+#[test]
+fn rep_times() {
+    let opts = Options {
+        num_reps: 3,
+        rep_times: vec![1, 2, 1],
+        relax_time: 0,
+        prep_time: 0,
+        ..Default::default()
+    };
+    let (result, frames, elapsed) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        [
+            "Rep 1/3 (1s)\n1s",
+            "Rep 2/3 (2s)\n2s",
+            "Rep 2/3 (2s)\n1s",
+            "Rep 3/3 (1s)\n1s",
+        ]
+    );
+    assert_eq!(elapsed, Duration::from_secs(4));
+}
+
+#[test]
+fn custom_messages() {
+    let opts = Options {
+        rep_message: Some("GO HARD {n} of {total}".to_string()),
+        relax_message: Some("Breathe".to_string()),
+        rep_time: 1,
+        prep_time: 0,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        ["GO HARD 1 of 2\n1s", "Breathe\n1s", "GO HARD 2 of 2\n1s",]
+    );
+}
+
+#[test]
+fn spinner() {
+    let opts = Options {
+        spinner: true,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    let first_second: Vec<String> = headers(&frames)
+        .into_iter()
+        .take_while(|frame| frame.starts_with("Starting in\n3s"))
+        .collect();
+    // One frame every 100ms
+    assert_eq!(
+        first_second,
+        ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+            .iter()
+            .map(|frame| format!("Starting in\n3s {}", frame))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn show_next() {
+    let opts = Options {
+        show_next: true,
+        rep_time: 1,
+        prep_time: 0,
+        cooldown: 1,
+        sets: 2,
+        set_rest: 1,
+        num_reps: 1,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        [
+            "Set 1/2\nRep 1/1\n1s",
+            "Relax!\nNext: Set 2/2, Rep 1/1\n1s",
+            "Set rest\nNext: Set 2/2, Rep 1/1\n1s",
+            "Set 2/2\nRep 1/1\n1s",
+            "Cooldown\n1s",
+        ]
+    );
+}
+
+#[test]
+fn show_next_at_the_end() {
+    let opts = Options {
+        show_next: true,
+        rep_time: 1,
+        prep_time: 0,
+        num_reps: 1,
+        trailing_rest: true,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(headers(&frames), ["Rep 1/1\n1s", "Relax!\nNext: Done\n1s"]);
+}
+
+#[test]
+fn colorblind_palette() {
+    let opts = Options {
+        palette: Palette::Colorblind,
+        speak: true,
+        rep_time: 1,
+        prep_time: 0,
+        num_reps: 1,
+        trailing_rest: true,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        [
+            "Says Rep 1 of 1",
+            "▲ Rep 1/1\n1s",
+            "Says Relax!",
+            "● Relax!\n1s",
+        ]
+    );
+}
+
+#[test]
+fn quiet() {
+    let opts = Options {
+        quiet: true,
+        center: true,
+        big: true,
+        prep_time: 1,
+        ..options()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        frames,
+        [
+            "Starting in 1s",
+            "Rep 1/2 2s",
+            "Rep 1/2 1s",
+            "Relax! 1s",
+            "Rep 2/2 2s",
+            "Rep 2/2 1s",
+        ]
+    );
+}
+
+/// Output that records the events only.
+struct EventRecorder {
+    events: Vec<String>,
+}
+
+impl Output for EventRecorder {
+    fn render(&mut self, _message: &str) {}
+
+    fn event(&mut self, event: &Event) {
+        self.events.push(match *event {
+            Event::Start {
+                phase,
+                index,
+                label,
+                duration,
+            } => format!("start {} {} {} {}s", index, phase, label, duration),
+            Event::Tick {
+                label,
+                elapsed,
+                remaining,
+            } => format!("tick {} {} {:?}", label, elapsed, remaining),
+            Event::Pause => "pause".to_string(),
+            Event::Resume => "resume".to_string(),
+        });
+    }
+}
+
+#[test]
+fn events() {
+    let opts = Options {
+        num_reps: 1,
+        prep_time: 0,
+        trailing_rest: true,
+        ..options()
+    };
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![
+            (Duration::from_millis(500), b' '),
+            (Duration::from_millis(1500), b' '),
+        ],
+    };
+    let mut output = EventRecorder { events: vec![] };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &opts,
+        &mut Stats::default(),
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        output.events,
+        [
+            "start 1 rep Rep 1/1 2s",
+            "tick Rep 1/1 0 Some(2)",
+            "pause",
+            "resume",
+            "tick Rep 1/1 1 Some(1)",
+            "start 2 relax Relax! 1s",
+            "tick Relax! 0 Some(1)",
+        ]
+    );
+}
+
+/// Input failing with an error of the given kind.
+struct FailingInput(std::io::ErrorKind);
+
+impl Input for FailingInput {
+    fn next_key(&mut self) -> Option<std::io::Result<u8>> {
+        Some(Err(std::io::Error::new(self.0, "stdin is closed")))
+    }
+}
+
+fn run_failing(kind: std::io::ErrorKind) -> Result<(), RepsError> {
+    let clock = FakeClock::new();
+    let mut output = Recorder {
+        frames: vec![],
+        width: 80,
+    };
+    start_reps(
+        &mut Io {
+            input: &mut FailingInput(kind),
+            output: &mut output,
+            clock: &*clock,
+        },
+        &options(),
+        &mut Stats::default(),
+    )
+}
+
+#[test]
+fn input_errors() {
+    let result = run_failing(std::io::ErrorKind::BrokenPipe);
+    assert_eq!(
+        result,
+        Err(RepsError::Io(std::io::Error::new(
+            std::io::ErrorKind::BrokenPipe,
+            "stdin is closed"
+        )))
+    );
+    assert_eq!(result.unwrap_err().to_string(), "Error: stdin is closed");
+    // Stopped from elsewhere, as if the user pressed ESC
+    assert_eq!(
+        run_failing(std::io::ErrorKind::Interrupted),
+        Err(RepsError::UserExit)
+    );
+}
+
+/// Input failing once with each of the errors, before reading the keys.
+struct FlakyInput {
+    errors: Vec<std::io::ErrorKind>,
+    input: FakeInput,
+}
+
+impl Input for FlakyInput {
+    fn next_key(&mut self) -> Option<std::io::Result<u8>> {
+        if self.errors.is_empty() {
+            return self.input.next_key();
+        }
+        let kind = self.errors.remove(0);
+        Some(Err(std::io::Error::new(kind, "try again")))
+    }
+}
+
+#[test]
+fn transient_input_errors() {
+    let clock = FakeClock::new();
+    let mut input = FlakyInput {
+        errors: vec![std::io::ErrorKind::WouldBlock, std::io::ErrorKind::TimedOut],
+        input: FakeInput {
+            clock: clock.clone(),
+            keys: vec![(Duration::from_millis(3500), b'n')],
+        },
+    };
+    let mut output = Recorder {
+        frames: vec![],
+        width: 80,
+    };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &options(),
+        &mut Stats::default(),
+    );
+
+    // The keys read after the errors still skip the rep
+    assert_eq!(result, Ok(()));
+    assert_eq!(clock.elapsed.get(), Duration::from_millis(6500));
+}
+
+/// Output that takes some time to draw each frame.
+struct SlowOutput {
+    clock: Rc<FakeClock>,
+}
+
+impl Output for SlowOutput {
+    fn render(&mut self, _message: &str) {
+        self.clock
+            .elapsed
+            .set(self.clock.elapsed.get() + Duration::from_millis(7));
+    }
+}
+
+#[test]
+fn no_drift() {
+    let opts = Options {
+        num_reps: 1,
+        rep_time: 600,
+        relax_time: 0,
+        prep_time: 0,
+        ..options()
+    };
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![],
+    };
+    let mut output = SlowOutput {
+        clock: clock.clone(),
+    };
+    let mut stats = Stats::default();
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &opts,
+        &mut stats,
+    );
+
+    assert_eq!(result, Ok(()));
+    // 600 frames take 4.2s to draw, they must not delay the end of the workout
+    let elapsed = clock.elapsed.get();
+    assert!(elapsed >= Duration::from_secs(600), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(600_050), "{:?}", elapsed);
+    assert_eq!(stats.work_time, elapsed);
+}
+
+#[test]
+fn pause_mid_second() {
+    let opts = Options {
+        num_reps: 1,
+        prep_time: 0,
+        ..options()
+    };
+    let mut stats = Stats::default();
+    let (result, frames, elapsed) = run_with_stats(
+        &opts,
+        vec![
+            (Duration::from_millis(400), b' '),
+            (Duration::from_millis(2400), b' '),
+        ],
+        80,
+        &mut stats,
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        [
+            "Rep 1/1\n2s",
+            "PAUSED 0:00\nBack to Rep 1/1, 2s left",
+            "PAUSED 0:01\nBack to Rep 1/1, 2s left",
+            "Rep 1/1\n2s",
+            "Rep 1/1\n1s"
+        ]
+    );
+    // The 0.6s left in the first second are waited for after the pause, not a whole second
+    assert_eq!(stats.work_time, Duration::from_secs(2));
+    assert_eq!(stats.rest_time, Duration::ZERO);
+    assert_eq!(elapsed, Duration::from_secs(4));
+}
+
+#[test]
+fn until_stop() {
+    let opts = Options {
+        num_reps: 0,
+        cooldown: 5,
+        ..options()
+    };
+    let mut stats = Stats::default();
+    let keys = vec![(Duration::from_millis(13500), 27)];
+    let (result, frames, _) = run_with_stats(&opts, keys, 80, &mut stats);
+
+    assert_eq!(result, Err(RepsError::UserExit));
+    assert_eq!(
+        headers(&frames)[3..],
+        [
+            "Rep 1\n2s",
+            "Rep 1\n1s",
+            "Relax!\n1s",
+            "Rep 2\n2s",
+            "Rep 2\n1s",
+            "Relax!\n1s",
+            "Rep 3\n2s",
+            "Rep 3\n1s",
+            "Relax!\n1s",
+            "Rep 4\n2s",
+            "Rep 4\n1s",
+        ]
+    );
+    assert_eq!(stats.reps, 3);
+    // The total duration is unknown
+    assert!(frames[0].contains("Elapsed 0:00"));
+    assert!(!frames.iter().any(|frame| frame.contains("Total")));
+}
+
+#[test]
+fn until_stop_back_to_back() {
+    let opts = Options {
+        num_reps: 0,
+        relax_time: 0,
+        ..options()
+    };
+    let mut stats = Stats::default();
+    let keys = vec![(Duration::from_millis(7500), 27)];
+    let (result, frames, _) = run_with_stats(&opts, keys, 80, &mut stats);
+
+    assert_eq!(result, Err(RepsError::UserExit));
+    // Without any relax in between
+    assert_eq!(
+        headers(&frames)[3..],
+        [
+            "Rep 1\n2s",
+            "Rep 1\n1s",
+            "Rep 2\n2s",
+            "Rep 2\n1s",
+            "Rep 3\n2s"
+        ]
+    );
+    assert_eq!(stats.reps, 2);
+}
+
+#[test]
+fn circuit() {
+    let exercise = |name: &str| Exercise {
+        name: name.to_string(),
+        reps: 1,
+        rep_time: 1,
+        relax_time: 1,
+    };
+    let opts = Options {
+        exercises: vec![exercise("Squats"), exercise("Plank")],
+        circuit: true,
+        sets: 2,
+        ..Default::default()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        [
+            "Squats – Round 1/2\n1s",
+            "Relax!\n1s",
+            "Plank – Round 1/2\n1s",
+            "Relax!\n1s",
+            "Squats – Round 2/2\n1s",
+            "Relax!\n1s",
+            "Plank – Round 2/2\n1s",
+        ]
+    );
+}
+
+/// Output that records when the bell rings.
+struct Bells {
+    clock: Rc<FakeClock>,
+    rings: Vec<Duration>,
+}
+
+impl Output for Bells {
+    fn render(&mut self, _message: &str) {}
+
+    fn bell(&mut self) {
+        self.rings.push(self.clock.elapsed.get());
+    }
+}
+
+#[test]
+fn fanfare() {
+    let opts = Options {
+        bell: true,
+        num_reps: 1,
+        rep_time: 1,
+        relax_time: 1,
+        prep_time: 0,
+        ..Default::default()
+    };
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![],
+    };
+    let mut output = Bells {
+        clock: clock.clone(),
+        rings: vec![],
+    };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &opts,
+        &mut Stats::default(),
+    );
+
+    assert_eq!(result, Ok(()));
+    let ms = Duration::from_millis;
+    // Twice at the start of the rep, once at its end, then 4 times after a silence
+    assert_eq!(
+        output.rings,
+        [
+            ms(0),
+            ms(200),
+            ms(1200),
+            ms(1700),
+            ms(1820),
+            ms(1940),
+            ms(2440)
+        ]
+    );
+}
+
+#[test]
+fn get_ready() {
+    let opts = Options {
+        get_ready: true,
+        num_reps: 2,
+        rep_time: 5,
+        relax_time: 5,
+        prep_time: 5,
+        ..Default::default()
+    };
+    let rings = |keys| {
+        let clock = FakeClock::new();
+        let mut input = FakeInput {
+            clock: clock.clone(),
+            keys,
+        };
+        let mut output = Bells {
+            clock: clock.clone(),
+            rings: vec![],
+        };
+        let result = start_reps(
+            &mut Io {
+                input: &mut input,
+                output: &mut output,
+                clock: &*clock,
+            },
+            &opts,
+            &mut Stats::default(),
+        );
+        assert_eq!(result, Ok(()));
+        output.rings
+    };
+
+    // Only during the last 3 seconds of the relax, neither the prep nor the reps
+    let secs = Duration::from_secs;
+    assert_eq!(rings(vec![]), [secs(12), secs(13), secs(14)]);
+    // Muted like the other bells
+    assert_eq!(rings(vec![(Duration::from_millis(6500), b'm')]), []);
+}
+
+#[test]
+fn help() {
+    let keys = vec![
+        (Duration::from_millis(3500), b'?'),
+        (Duration::from_secs(10), b'x'),
+    ];
+    let (result, frames, elapsed) = run(&options(), keys);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames[4..6]),
+        [
+            concat!(
+                "Keys\n",
+                "SPACE  pause\n",
+                "    n  skip\n",
+                "    p  back\n",
+                "  +/-  add/remove 10s\n",
+                "    r  restart\n",
+                "    ?  help\n",
+                "  ESC  quit\n",
+                "Press any key to resume"
+            ),
+            "Rep 1/2\n2s"
+        ]
+    );
+    assert!(elapsed > Duration::from_secs(14));
+}
+
+#[test]
+fn help_line() {
+    let opts = Options {
+        help_line: true,
+        ..options()
+    };
+    let (_, frames, _) = run(&opts, vec![]);
+
+    // During the preparation, then during a rep
+    let last_lines = |frame: &String| {
+        let lines: Vec<&str> = frame.lines().collect();
+        assert_eq!(lines.len(), 24);
+        lines[22..].join("\n")
+    };
+    assert_eq!(
+        last_lines(&frames[0]),
+        format!(
+            "{}\n{}",
+            "─".repeat(80),
+            "SPACE pause · n skip · p back · +/- add/remove 10s · ? help · ESC quit"
+        )
+    );
+    // Too long to fit on 80 columns once the rep starts
+    let (_, frames, _) = run_with_width(&opts, vec![], 100);
+    assert!(last_lines(&frames[3]).ends_with("r restart · ? help · ESC quit"));
+
+    let (_, frames, _) = run_with_width(&opts, vec![], 20);
+    assert!(frames[0].ends_with("\nSPACE pause · n skip"));
+}
+
+#[test]
+fn custom_keys() {
+    let opts = Options {
+        keys: Keys {
+            pause: b'p',
+            quit: b'q',
+            ..Keys::default()
+        },
+        ..options()
+    };
+    // ESC and space do nothing anymore
+    let keys = vec![
+        (Duration::from_millis(3500), 27),
+        (Duration::from_millis(3600), b' '),
+        (Duration::from_millis(3700), b'p'),
+        (Duration::from_secs(10), b'p'),
+        (Duration::from_secs(12), b'q'),
+    ];
+    let (result, frames, elapsed) = run(&opts, keys);
+
+    assert_eq!(result, Err(RepsError::UserExit));
+    assert_eq!(
+        headers(&frames[3..5]),
+        ["Rep 1/2\n2s", "PAUSED 0:00\nBack to Rep 1/2, 2s left"]
+    );
+    assert_eq!(headers(&frames[11..12]), ["Rep 1/2\n2s"]);
+    assert!(elapsed < Duration::from_millis(12100));
+}
+
+#[test]
+fn precise() {
+    let opts = Options {
+        precise: true,
+        num_reps: 1,
+        rep_time: 1,
+        relax_time: 0,
+        prep_time: 0,
+        ..Default::default()
+    };
+    let (result, frames, elapsed) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    let expected: Vec<String> = (1..=10)
+        .rev()
+        .map(|tenths| format!("Rep 1/1\n{}.{}s", tenths / 10, tenths % 10))
+        .collect();
+    assert_eq!(headers(&frames), expected);
+    assert_eq!(elapsed, Duration::from_secs(1));
+}
+
+#[test]
+fn mute() {
+    let opts = Options {
+        bell: true,
+        num_reps: 2,
+        rep_time: 2,
+        relax_time: 1,
+        prep_time: 0,
+        ..Default::default()
+    };
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![
+            (Duration::from_secs(1), b'm'),
+            (Duration::from_secs(4), b'm'),
+        ],
+    };
+    let mut output = Bells {
+        clock: clock.clone(),
+        rings: vec![],
+    };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &opts,
+        &mut Stats::default(),
+    );
+
+    assert_eq!(result, Ok(()));
+    let ms = Duration::from_millis;
+    // Silent from the middle of the first rep to the middle of the second one, which starts
+    // without waiting between the bells
+    assert_eq!(
+        output.rings,
+        [
+            ms(0),
+            ms(200),
+            ms(5200),
+            ms(5700),
+            ms(5820),
+            ms(5940),
+            ms(6440)
+        ]
+    );
+
+    let keys = vec![(Duration::from_millis(3500), b'm')];
+    let (_, frames, _) = run(&options(), keys.clone());
+    assert_eq!(frames, run(&options(), vec![]).1);
+    let (_, frames, _) = run(
+        &Options {
+            bell: true,
+            ..options()
+        },
+        keys,
+    );
+    assert_eq!(headers(&frames[4..5]), ["Rep 1/2\n2s\nMuted"]);
+}
+
+#[test]
+fn resume() {
+    // In the middle of the second rep, after a prep
+    let opts = Options {
+        resume_at: Some((3, 1)),
+        ..options()
+    };
+    let (result, frames, elapsed) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        [
+            "Starting in\n3s",
+            "Starting in\n2s",
+            "Starting in\n1s",
+            "Rep 2/2\n1s",
+        ]
+    );
+    assert_eq!(elapsed, Duration::from_secs(4));
+    assert!(frames[3].contains("Elapsed 0:03 / Total 0:04"));
+
+    // During the prep, which is not done twice
+    let opts = Options {
+        resume_at: Some((0, 1)),
+        ..options()
+    };
+    let (_, frames, _) = run(&opts, vec![]);
+    assert_eq!(headers(&frames[..2]), ["Starting in\n1s", "Rep 1/2\n2s"]);
+}
+
+#[test]
+fn rep_dots() {
+    let dots = |frame: &String| {
+        frame
+            .lines()
+            .rfind(|line| line.contains(['●', '○']) || line.starts_with("Reps"))
+            .map(str::to_string)
+    };
+    let opts = Options {
+        num_reps: 3,
+        no_color: true,
+        trailing_rest: true,
+        ..options()
+    };
+    let (_, frames, _) = run(&opts, vec![]);
+    let mut seen: Vec<String> = frames.iter().filter_map(dots).collect();
+    seen.dedup();
+    assert_eq!(seen, ["○○○", "●○○", "●●○", "●●●"]);
+
+    // Too many reps to display them as dots
+    let opts = Options {
+        num_reps: 40,
+        no_color: true,
+        ..options()
+    };
+    let (_, frames, _) = run_with_width(&opts, vec![(Duration::from_millis(8500), 27)], 80);
+    assert_eq!(dots(frames.last().unwrap()).as_deref(), Some("Reps 2/40"));
+}
+
+#[test]
+fn wait_for_any_key() {
+    let wait = |opts: &Options, keys: Vec<(Duration, u8)>| {
+        let clock = FakeClock::new();
+        let mut input = FakeInput {
+            clock: clock.clone(),
+            keys,
+        };
+        let mut output = Recorder {
+            frames: vec![],
+            width: 80,
+        };
+        let result = wait_to_start(
+            &mut Io {
+                input: &mut input,
+                output: &mut output,
+                clock: &*clock,
+            },
+            opts,
+        );
+        (result, output.frames, clock.elapsed.get())
+    };
+    let opts = Options {
+        wait_for_key: true,
+        ..options()
+    };
+
+    let (result, frames, elapsed) = wait(&opts, vec![(Duration::from_secs(5), b'x')]);
+    assert_eq!(result, Ok(()));
+    assert_eq!(headers(&frames), ["Press any key to start"]);
+    assert_eq!(elapsed, Duration::from_secs(5));
+
+    let (result, _, elapsed) = wait(&opts, vec![(Duration::from_secs(2), 27)]);
+    assert_eq!(result, Err(RepsError::UserExit));
+    assert_eq!(elapsed, Duration::from_secs(2));
+
+    // The summary is displayed for a few seconds, or until a key is pressed
+    let summarized = |num_reps, wait_for_key| Options {
+        num_reps,
+        rep_time: 30,
+        relax_time: 10,
+        prep_time: 5,
+        summary: true,
+        wait_for_key,
+        ..options()
+    };
+    let (result, frames, elapsed) = wait(&summarized(3, false), vec![]);
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames),
+        ["3 reps\nWork 1:30, rest 0:20\nTotal 1:55"]
+    );
+    assert_eq!(elapsed, SUMMARY_DURATION);
+    let (_, _, elapsed) = wait(&summarized(3, false), vec![(Duration::from_secs(1), b'x')]);
+    assert_eq!(elapsed, Duration::from_secs(1));
+    let (_, frames, elapsed) = wait(&summarized(3, true), vec![(Duration::from_secs(5), b'x')]);
+    assert_eq!(
+        headers(&frames),
+        ["3 reps\nWork 1:30, rest 0:20\nTotal 1:55\nPress any key to start"]
+    );
+    assert_eq!(elapsed, Duration::from_secs(5));
+
+    // Nothing to wait for, and no end to summarize
+    let (_, frames, _) = wait(&options(), vec![]);
+    assert!(frames.is_empty());
+    let (_, frames, _) = wait(&summarized(0, false), vec![]);
+    assert!(frames.is_empty());
+}
+
+#[test]
+fn minutes_and_seconds() {
+    let remaining = |opts: &Options| {
+        let (_, frames, _) = run(opts, vec![(Duration::from_millis(1500), 27)]);
+        headers(&frames)
+            .iter()
+            .map(|header| header.lines().nth(1).unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let opts = |rep_time| Options {
+        num_reps: 1,
+        rep_time,
+        prep_time: 0,
+        ..options()
+    };
+    assert_eq!(remaining(&opts(2)), ["2s", "1s"]);
+    assert_eq!(remaining(&opts(90)), ["1:30", "1:29"]);
+    // The width stays the same until the end
+    assert_eq!(remaining(&opts(600)), ["10:00", "09:59"]);
+    assert_eq!(remaining(&opts(3600)), ["1:00:00", "0:59:59"]);
+    assert_eq!(
+        remaining(&Options {
+            always_mmss: true,
+            ..opts(2)
+        }),
+        ["0:02", "0:01"]
+    );
+    assert_eq!(
+        remaining(&Options {
+            precise: true,
+            ..opts(90)
+        })[..2],
+        ["1:30.0", "1:29.9"]
+    );
+}
+
+#[test]
+fn flash() {
+    let opts = Options {
+        cue_intensity: CueIntensity::Strong,
+        ..options()
+    };
+    let (result, frames, elapsed) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    // Once before each countdown, without making the workout longer
+    let filled = " ".repeat(80);
+    let (flashes, frames): (Vec<String>, Vec<String>) = frames
+        .into_iter()
+        .partition(|frame| frame.lines().all(|line| line == filled));
+    let (_, without, baseline) = run(&options(), vec![]);
+    assert_eq!(flashes.len(), 4);
+    assert_eq!(headers(&frames), headers(&without));
+    assert_eq!(elapsed, baseline);
+}
+
+#[test]
+fn no_visual_cue() {
+    let opts = Options {
+        cadence: 60,
+        cue_intensity: CueIntensity::None,
+        ..options()
+    };
+    let (result, frames, elapsed) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    // The beats are only heard
+    let (_, without, baseline) = run(&options(), vec![]);
+    assert_eq!(frames, without);
+    assert_eq!(elapsed, baseline);
+}
+
+#[test]
+fn motivation() {
+    let opts = Options {
+        num_reps: 2,
+        rep_time: 6,
+        relax_time: 0,
+        prep_time: 1,
+        motivation: vec!["Push!".to_string(), "Breathe".to_string()],
+        ..Default::default()
+    };
+    let (result, frames, _) = run(&opts, vec![]);
+
+    assert_eq!(result, Ok(()));
+    let phrases: Vec<&str> = frames
+        .iter()
+        .map(|frame| frame.lines().nth(2).unwrap())
+        .collect();
+    // Only during the reps, the next one starting with the next phrase
+    assert!(!frames[0].contains("Push!"));
+    assert_eq!(
+        phrases[1..=12],
+        [
+            "Push!", "Push!", "Push!", "Push!", "Push!", "Breathe", "Breathe", "Breathe",
+            "Breathe", "Breathe", "Breathe", "Push!"
+        ]
+    );
+    let big = Options { big: true, ..opts };
+    let (_, frames, _) = run(&big, vec![]);
+    assert!(frames.iter().all(|frame| !frame.contains("Push!")));
+}
+
+#[test]
+fn previous() {
+    let opts = Options {
+        relax_time: 2,
+        ..options()
+    };
+    // Back to the first rep during the relax, then from the second rep to the prep, which is
+    // started again
+    let keys = vec![
+        (Duration::from_millis(5500), b'p'),
+        (Duration::from_millis(10500), b'p'),
+        (Duration::from_millis(10600), b'p'),
+        (Duration::from_millis(10700), b'p'),
+        (Duration::from_millis(10800), b'p'),
+    ];
+    let mut stats = Stats::default();
+    let (result, frames, _) = run_with_stats(&opts, keys, 80, &mut stats);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames)[..16],
+        [
+            "Starting in\n3s",
+            "Starting in\n2s",
+            "Starting in\n1s",
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Relax!\n2s",
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Relax!\n2s",
+            "Relax!\n1s",
+            "Rep 2/2\n2s",
+            "Relax!\n2s",
+            "Rep 1/2\n2s",
+            "Starting in\n3s",
+            "Starting in\n3s",
+            "Starting in\n2s",
+        ]
+    );
+    // Each rep is only counted once, even if it was done again
+    assert_eq!(stats.reps, 2);
+}
+
+#[test]
+fn ready_to_end_the_rest() {
+    let opts = Options {
+        relax_time: 10,
+        ..options()
+    };
+    // Nothing happens during the prep and the reps
+    let keys = vec![
+        (Duration::from_millis(1500), b'\r'),
+        (Duration::from_millis(4500), b'\r'),
+        (Duration::from_millis(6500), b'\r'),
+    ];
+    let mut stats = Stats::default();
+    let (result, frames, elapsed) = run_with_stats(&opts, keys, 80, &mut stats);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        headers(&frames)[..9],
+        [
+            "Starting in\n3s",
+            "Starting in\n2s",
+            "Starting in\n1s",
+            "Rep 1/2\n2s",
+            "Rep 1/2\n1s",
+            "Relax!\n10s",
+            "Relax!\n9s",
+            "Rep 2/2\n2s",
+            "Rep 2/2\n1s",
+        ]
+    );
+    assert_eq!(stats.reps, 2);
+    assert_eq!(elapsed, Duration::from_millis(8500));
+}
+
+#[test]
+fn wrap_long_labels() {
+    let opts = Options {
+        num_reps: 1,
+        rep_label: Some("Alternating dumbbell curls".to_string()),
+        center: true,
+        prep_time: 0,
+        ..options()
+    };
+    let (result, frames, _) = run_with_width(&opts, vec![], 12);
+
+    assert_eq!(result, Ok(()));
+    let lines: Vec<&str> = frames[0].lines().filter(|line| !line.is_empty()).collect();
+    assert_eq!(
+        lines[..5],
+        [
+            "Alternating",
+            "  dumbbell",
+            " curls 1/1",
+            "     2s",
+            "[----------]"
+        ]
+    );
+}
+
+/// Output that only records the diagnostics.
+struct Logs {
+    lines: Vec<String>,
+}
+
+impl Output for Logs {
+    fn render(&mut self, _message: &str) {}
+
+    fn log(&mut self, message: &str) {
+        self.lines.push(message.to_string());
+    }
+}
+
+#[test]
+fn log_the_end_of_each_phase() {
+    let clock = FakeClock::new();
+    let mut input = FakeInput {
+        clock: clock.clone(),
+        keys: vec![(Duration::from_millis(3500), b'n')],
+    };
+    let mut output = Logs { lines: vec![] };
+    let result = start_reps(
+        &mut Io {
+            input: &mut input,
+            output: &mut output,
+            clock: &*clock,
+        },
+        &options(),
+        &mut Stats::default(),
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        output.lines,
+        [
+            "End of prep 1: Completed",
+            "End of rep 2: Skipped",
+            "End of relax 3: Completed",
+            "End of rep 4: Completed"
+        ]
+    );
+}
+
+#[test]
+fn tui() {
+    let opts = Options {
+        tui: true,
+        no_color: true,
+        ..options()
+    };
+    let (result, frames, _) = run_with_width(&opts, vec![], 40);
+
+    assert_eq!(result, Ok(()));
+    let lines: Vec<&str> = frames[4].lines().collect();
+    assert_eq!(
+        lines[..11],
+        [
+            "Rep 1/2",
+            "1s",
+            "██████████████████░░░░░░░░░░░░░░░░░  50%",
+            "Elapsed 0:04 · Remaining 0:04",
+            "",
+            "Schedule",
+            "  Starting in  0:03",
+            "▶ Rep 1/2      0:02",
+            "  Relax!       0:01",
+            "  Rep 2/2      0:02",
+            "",
+        ]
+    );
+    assert_eq!(lines.len(), 24);
+    assert_eq!(lines[23], "SPACE pause · n skip · p back · +/- add/");
+
+    // The list follows the current phase, the one before it stays in sight
+    let opts = Options {
+        num_reps: 20,
+        ..opts
+    };
+    let (_, frames, _) = run_with_width(&opts, vec![(Duration::from_secs(38), 27)], 40);
+    let lines: Vec<&str> = frames.last().unwrap().lines().collect();
+    assert_eq!(lines[3], "Elapsed 0:37 · Remaining 0:25");
+    assert_eq!(lines[6], "  Relax!       0:01");
+    assert_eq!(lines[7], "▶ Rep 12/20    0:02");
+    assert_eq!(lines[21], "  Rep 19/20    0:02");
+}
+
+/// Output that records the rendered messages as they are, with their escape sequences.
+struct Screens {
+    frames: Vec<String>,
+}
+
+impl Output for Screens {
+    fn render(&mut self, message: &str) {
+        self.frames.push(message.to_string());
+    }
+}
+
+#[test]
+fn tui_rendering() {
+    // The gauge spans the whole width of the screen
+    let opts = Options {
+        tui: true,
+        no_color: true,
+        ..options()
+    };
+    for width in [20, 40, 80] {
+        let (_, frames, _) = run_with_width(&opts, vec![], width);
+        let gauge = frames[4].lines().nth(2).unwrap();
+        assert!(gauge.starts_with('█') && gauge.ends_with(" 50%"));
+        assert_eq!(gauge.chars().count(), width as usize);
+    }
+
+    // The current phase is bold and has the color of the countdown, the ones done are dimmed
+    let clock = FakeClock::new();
+    let mut output = Screens { frames: vec![] };
+    let result = start_reps(
+        &mut Io {
+            input: &mut FakeInput {
+                clock: clock.clone(),
+                keys: vec![],
+            },
+            output: &mut output,
+            clock: &*clock,
+        },
+        &Options {
+            tui: true,
+            ..options()
+        },
+        &mut Stats::default(),
+    );
+
+    assert_eq!(result, Ok(()));
+    let line = |text: &str| {
+        output.frames[4]
+            .lines()
+            .find(|line| line.contains(text))
+            .unwrap()
+            .to_string()
+    };
+    assert!(line("Starting in  0:03").contains("\x1b[2m"));
+    let current = line("Rep 1/2      0:02");
+    assert!(current.contains("\x1b[1m\x1b[38;5;1m▶ "));
+    let next = line("Relax!       0:01");
+    assert!(!next.contains("\x1b[1m") && !next.contains("\x1b[2m"));
+}
@@ -0,0 +1,89 @@
+extern crate reps;
+
+use std::cell::{Cell, RefCell};
+use std::io::{self, Write};
+use std::panic;
+use std::rc::Rc;
+
+use reps::{Options, RawMode, TerminalGuard};
+
+/// Terminal recording what is written, and whether the raw mode was suspended.
+#[derive(Clone, Default)]
+struct FakeTerminal {
+    written: Rc<RefCell<Vec<u8>>>,
+    suspended: Rc<Cell<bool>>,
+}
+
+impl Write for FakeTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl RawMode for FakeTerminal {
+    fn suspend_raw_mode(&self) -> io::Result<()> {
+        self.suspended.set(true);
+        Ok(())
+    }
+}
+
+impl FakeTerminal {
+    fn output(&self) -> String {
+        String::from_utf8(self.written.borrow().clone()).unwrap()
+    }
+}
+
+fn fails(terminal: FakeTerminal) -> Result<(), String> {
+    let _guard = TerminalGuard::new(terminal, &Options::default());
+    Err("Error: stdin is closed".to_string())
+}
+
+#[test]
+fn restored_on_error() {
+    let terminal = FakeTerminal::default();
+    assert!(fails(terminal.clone()).is_err());
+
+    assert!(terminal.suspended.get());
+    let output = terminal.output();
+    // Hidden on creation, then shown again
+    assert!(output.starts_with("\x1b[2J\x1b[?25l"));
+    assert!(output.ends_with("\x1b[2J\x1b[1;1H\x1b[?25h\x1b[39m"));
+}
+
+#[test]
+fn bold_is_reset() {
+    let terminal = FakeTerminal::default();
+    let opts = Options {
+        bold: true,
+        ..Default::default()
+    };
+    drop(TerminalGuard::new(terminal.clone(), &opts));
+
+    assert!(terminal.output().ends_with("\x1b[?25h\x1b[39m\x1b[m"));
+}
+
+#[test]
+fn restored_on_panic() {
+    let terminal = FakeTerminal::default();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let opts = Options {
+            no_color: true,
+            set_title: true,
+            ..Default::default()
+        };
+        let _guard = TerminalGuard::new(terminal.clone(), &opts);
+        panic!("Something went wrong");
+    }));
+
+    assert!(result.is_err());
+    assert!(terminal.suspended.get());
+    // The screen is not cleared, so that the panic message can be read
+    assert!(terminal
+        .output()
+        .ends_with("\x1b[2J\x1b[?25l\x1b[?25h\x1b]0;\x07"));
+}